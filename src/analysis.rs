@@ -0,0 +1,157 @@
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+
+use crate::checkers::{Checkers, Move};
+use crate::evaluator::Evaluator;
+use crate::game::Game;
+use crate::pdn::{move_to_notation, PdnGame};
+use crate::search::{best_move_with_stats, SearchOptions, WIN_SCORE};
+
+// Where the GUI saves the annotated record of the last game.
+pub const ANALYSIS_FILE: &str = "last_game_analysis.pdn";
+
+// How much worse than the engine's choice a move has to be, in evaluation
+// points for the side that played it. A man is worth about 5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub inaccuracy: i32,
+    pub mistake: i32,
+    pub blunder: i32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            inaccuracy: 2,
+            mistake: 5,
+            blunder: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "inaccuracy",
+            Judgement::Mistake => "mistake",
+            Judgement::Blunder => "blunder",
+        }
+    }
+
+    // The annotation glyph written after the move in PDN.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// One analysed move. Evaluations are from the point of view of the side that
+// played it, before the move (with best play) and after the move actually
+// played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveAnalysis {
+    pub ply: usize,
+    pub played: (usize, usize, Move),
+    pub best: Option<(usize, usize, Move)>,
+    pub eval_before: i32,
+    pub eval_after: i32,
+    pub judgement: Option<Judgement>,
+}
+
+impl MoveAnalysis {
+    // Evaluation points given away by the move. Mate scores are capped so a
+    // missed win counts as a large but finite loss.
+    pub fn loss(&self) -> i32 {
+        let cap = |score: i32| score.clamp(-100, 100);
+        (cap(self.eval_before) - cap(self.eval_after)).max(0)
+    }
+
+    // PDN annotation: the glyph and a comment with the engine's choice.
+    pub fn annotation(&self) -> String {
+        match (self.judgement, &self.best) {
+            (Some(judgement), Some((j, i, m))) => format!(
+                "{} {{{}: {} was better ({} instead of {})}}",
+                judgement.symbol(), judgement, move_to_notation(*j, *i, m), self.eval_before, self.eval_after,
+            ),
+            _ => String::new(),
+        }
+    }
+}
+
+// Score for the side to move, or a lost score if it has no moves.
+fn search_position(board: &Checkers, options: &SearchOptions, evaluator: &dyn Evaluator) -> (i32, Option<(usize, usize, Move)>) {
+    if board.get_all_moves().is_empty() {
+        return (-WIN_SCORE, None);
+    }
+    let (best, stats) = best_move_with_stats(board, options, evaluator, &AtomicBool::new(false), &mut |_| ());
+    let score = stats.iterations.last().map_or(0, |iteration| iteration.score);
+    (score, best.ok())
+}
+
+// Searches every position of the game once. A position's score is the
+// "before" evaluation of the move played from it and, negated, the "after"
+// evaluation of the move that led to it. `progress` gets the number of
+// positions searched so far and the total.
+pub fn analyze_game(game: &Game, options: &SearchOptions, evaluator: &dyn Evaluator, thresholds: &Thresholds, mut progress: impl FnMut(usize, usize)) -> Vec<MoveAnalysis> {
+    let options = SearchOptions { eval_noise: 0, blunder_chance: 0., multi_pv: 1, ..*options };
+    let mut positions = vec![game.start];
+    for (j, i, m) in &game.history {
+        let mut next = *positions.last().expect("start position");
+        next.make_move_from_enum(*j, *i, m).expect("recorded move failed");
+        positions.push(next);
+    }
+    let mut searched = Vec::with_capacity(positions.len());
+    for (index, board) in positions.iter().enumerate() {
+        searched.push(search_position(board, &options, evaluator));
+        progress(index + 1, positions.len());
+    }
+
+    game.history.iter().enumerate().map(|(ply, played)| {
+        let (eval_before, best) = searched[ply].clone();
+        let eval_after = -searched[ply + 1].0;
+        let mut analysis = MoveAnalysis { ply, played: played.clone(), best, eval_before, eval_after, judgement: None };
+        let loss = analysis.loss();
+        let is_best = analysis.best.as_ref() == Some(played);
+        analysis.judgement = match loss {
+            _ if is_best => None,
+            loss if loss >= thresholds.blunder => Some(Judgement::Blunder),
+            loss if loss >= thresholds.mistake => Some(Judgement::Mistake),
+            loss if loss >= thresholds.inaccuracy => Some(Judgement::Inaccuracy),
+            _ => None,
+        };
+        analysis
+    }).collect()
+}
+
+// The game as PDN with every flagged move annotated.
+pub fn annotated_pdn(record: &PdnGame, analysis: &[MoveAnalysis]) -> String {
+    let annotations: Vec<String> = analysis.iter().map(MoveAnalysis::annotation).collect();
+    record.to_annotated_pdn(&annotations)
+}
+
+// Counts of inaccuracies, mistakes and blunders for black and for red.
+pub fn summary(game: &Game, analysis: &[MoveAnalysis]) -> String {
+    let black_first = game.start.current_turn > 0;
+    let count = |black: bool, judgement: Judgement| analysis.iter()
+        .filter(|a| ((a.ply + 1) % 2 == 1) == (black == black_first) && a.judgement == Some(judgement))
+        .count();
+    let side = |black: bool| format!("{} inaccuracies, {} mistakes, {} blunders",
+        count(black, Judgement::Inaccuracy), count(black, Judgement::Mistake), count(black, Judgement::Blunder));
+    format!("black: {}; red: {}", side(true), side(false))
+}
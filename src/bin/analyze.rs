@@ -0,0 +1,50 @@
+// Usage: analyze <games.pdn> [depth] [output_file]
+// Writes every game of the collection again with inaccuracies, mistakes and
+// blunders annotated.
+use hw4::analysis::{analyze_game, annotated_pdn, summary, Thresholds};
+use hw4::evaluator::{WeightedEvaluator, WEIGHTS_FILE};
+use hw4::pdn::{parse_pdn, PdnGame};
+use hw4::search::{default_threads, SearchOptions};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(input) = args.get(1) else {
+        eprintln!("usage: analyze <games.pdn> [depth] [output_file]");
+        std::process::exit(2);
+    };
+    let depth: usize = args.get(2).and_then(|a| a.parse().ok()).unwrap_or(8);
+    let output = args.get(3).cloned().unwrap_or_else(|| format!("{}.analysis.pdn", input.trim_end_matches(".pdn")));
+
+    let text = match std::fs::read_to_string(input) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("could not read {}: {}", input, e);
+            std::process::exit(1);
+        }
+    };
+    let evaluator = WeightedEvaluator::load(WEIGHTS_FILE).unwrap_or_default();
+    let options = SearchOptions { threads: default_threads(), ..SearchOptions::fixed_depth(depth) };
+    let mut annotated = String::new();
+    for (number, pdn_game) in parse_pdn(&text).iter().enumerate() {
+        let game = pdn_game.replay();
+        let analysis = analyze_game(&game, &options, &evaluator, &Thresholds::default(), |_, _| ());
+        println!("game {}: {}", number + 1, summary(&game, &analysis));
+        if !annotated.is_empty() {
+            annotated.push('\n');
+        }
+        // keep the collection's tags and result; the replay may stop early
+        let mut record = PdnGame::from_game(&game);
+        for (name, value) in &pdn_game.tags {
+            if record.tag(name).is_none() {
+                record.tags.push((name.clone(), value.clone()));
+            }
+        }
+        record.result = pdn_game.result;
+        annotated.push_str(&annotated_pdn(&record, &analysis));
+    }
+    if let Err(e) = std::fs::write(&output, annotated) {
+        eprintln!("could not write {}: {}", output, e);
+        std::process::exit(1);
+    }
+    println!("wrote {}", output);
+}
@@ -0,0 +1,27 @@
+// Usage: build_book <games.pdn> [max_plies] [output_file]
+use hw4::book::{OpeningBook, BOOK_FILE};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(input) = args.get(1) else {
+        eprintln!("usage: build_book <games.pdn> [max_plies] [output_file]");
+        std::process::exit(2);
+    };
+    let max_plies: usize = args.get(2).and_then(|a| a.parse().ok()).unwrap_or(12);
+    let output = args.get(3).map(String::as_str).unwrap_or(BOOK_FILE);
+
+    let text = match std::fs::read_to_string(input) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("could not read {}: {}", input, e);
+            std::process::exit(1);
+        }
+    };
+    let book = OpeningBook::from_pdn(&text, max_plies);
+    println!("book has {} positions", book.len());
+    if let Err(e) = book.save(output) {
+        eprintln!("could not write {}: {}", output, e);
+        std::process::exit(1);
+    }
+    println!("wrote {}", output);
+}
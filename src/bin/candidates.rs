@@ -0,0 +1,31 @@
+// Usage: candidates [fen] [lines] [depth]
+// Prints the best few moves of a position with their scores and the lines
+// the engine expects, for comparing candidate moves. Scores are for the side
+// to move; the default position is the start.
+use std::sync::atomic::AtomicBool;
+
+use hw4::evaluator::{WeightedEvaluator, WEIGHTS_FILE};
+use hw4::pdn::{from_fen, START_FEN};
+use hw4::search::{default_threads, multi_pv, SearchOptions};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let fen = args.get(1).map_or(START_FEN, String::as_str);
+    let Some(board) = from_fen(fen) else {
+        eprintln!("usage: candidates [fen] [lines] [depth]");
+        std::process::exit(2);
+    };
+    let lines: usize = args.get(2).and_then(|a| a.parse().ok()).unwrap_or(3);
+    let depth: usize = args.get(3).and_then(|a| a.parse().ok()).unwrap_or(10);
+
+    let evaluator = WeightedEvaluator::load(WEIGHTS_FILE).unwrap_or_default();
+    let options = SearchOptions { threads: default_threads(), ..SearchOptions::fixed_depth(depth) };
+    let (pv, stats) = multi_pv(&board, &options, &evaluator, lines, &AtomicBool::new(false));
+    if pv.is_empty() {
+        println!("no legal moves");
+    }
+    for (rank, line) in pv.iter().enumerate() {
+        println!("{}. {}", rank + 1, line);
+    }
+    println!("{}", stats);
+}
@@ -0,0 +1,73 @@
+// Usage: dxp host [port] [depth]
+//        dxp connect <address> [black|red] [depth]
+// Plays one game against another engine over the DamExchange Protocol.
+// The host waits for a game request and follows; connect sends the request
+// and plays the given color. Each move gets its share of the agreed time,
+// searching no deeper than the depth. Loads the weights and tablebase files
+// when they are present.
+use std::net::{TcpListener, TcpStream};
+
+use hw4::dxp::{DxpError, DxpSession, DEFAULT_PORT};
+use hw4::evaluator::{WeightedEvaluator, WEIGHTS_FILE};
+use hw4::pdn::move_to_notation;
+use hw4::player::AlphaBetaPlayer;
+use hw4::search::SearchOptions;
+use hw4::tablebase::{Tablebase, TABLEBASE_FILE};
+
+const ENGINE_NAME: &str = "HW4";
+const DEFAULT_DEPTH: usize = 20;
+
+fn usage() -> ! {
+    eprintln!("usage: dxp host [port] [depth]");
+    eprintln!("       dxp connect <address> [black|red] [depth]");
+    std::process::exit(2);
+}
+
+fn open(args: &[String]) -> Result<(DxpSession, usize), DxpError> {
+    match args.get(1).map(String::as_str) {
+        Some("host") => {
+            let port = args.get(2).and_then(|a| a.parse().ok()).unwrap_or(DEFAULT_PORT);
+            let depth = args.get(3).and_then(|a| a.parse().ok()).unwrap_or(DEFAULT_DEPTH);
+            let listener = TcpListener::bind(("0.0.0.0", port))?;
+            println!("waiting on port {}", port);
+            let (stream, address) = listener.accept()?;
+            println!("connection from {}", address);
+            Ok((DxpSession::accept(stream, ENGINE_NAME)?, depth))
+        },
+        Some("connect") => {
+            let Some(address) = args.get(2) else { usage() };
+            let color = match args.get(3).map(String::as_str) {
+                Some("red") => -1,
+                Some("black") | None => 1,
+                Some(_) => usage(),
+            };
+            let depth = args.get(4).and_then(|a| a.parse().ok()).unwrap_or(DEFAULT_DEPTH);
+            let stream = TcpStream::connect(address.as_str())?;
+            Ok((DxpSession::initiate(stream, ENGINE_NAME, color, 10, 50, None)?, depth))
+        },
+        _ => usage(),
+    }
+}
+
+fn main() {
+    let tablebase = Tablebase::load_static(TABLEBASE_FILE).ok();
+    let args: Vec<String> = std::env::args().collect();
+    let result = open(&args).and_then(|(mut session, depth)| {
+        let side = if session.color > 0 { "black" } else { "red" };
+        println!("playing {} against {}", side, session.opponent);
+        let evaluator = WeightedEvaluator::load(WEIGHTS_FILE).unwrap_or_default();
+        let options = SearchOptions { tablebase, ..session.search_options(depth) };
+        let mut player = AlphaBetaPlayer::with_options(options, Box::new(evaluator));
+        let result = session.play(&mut player)?;
+        let moves: Vec<String> = session.game.history.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect();
+        println!("{}", moves.join(" "));
+        Ok(result)
+    });
+    match result {
+        Ok(result) => println!("{:?}", result),
+        Err(e) => {
+            eprintln!("dxp: {}", e);
+            std::process::exit(1);
+        },
+    }
+}
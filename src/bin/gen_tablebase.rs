@@ -0,0 +1,19 @@
+// Usage: gen_tablebase [max_pieces] [output_file]
+use std::time::Instant;
+
+use hw4::tablebase::{self, TABLEBASE_FILE};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let max_pieces: usize = args.get(1).and_then(|a| a.parse().ok()).unwrap_or(3);
+    let path = args.get(2).map(String::as_str).unwrap_or(TABLEBASE_FILE);
+
+    let start = Instant::now();
+    let tb = tablebase::generate(max_pieces);
+    println!("solved up to {} pieces in {:?}: {} decisive positions", tb.max_pieces, start.elapsed(), tb.len());
+    if let Err(e) = tb.save(path) {
+        eprintln!("could not write {}: {}", path, e);
+        std::process::exit(1);
+    }
+    println!("wrote {}", path);
+}
@@ -0,0 +1,33 @@
+// Usage: hub
+// A headless engine speaking the Hub protocol on stdin and stdout, for
+// draughts GUIs and scripts. Loads the weights, opening book and tablebase
+// files when they are present.
+use std::io::{self, BufRead};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+
+use hw4::book::{OpeningBook, BOOK_FILE};
+use hw4::evaluator::{WeightedEvaluator, WEIGHTS_FILE};
+use hw4::hub::{run, HubEngine};
+use hw4::tablebase::{Tablebase, TABLEBASE_FILE};
+
+fn main() {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let evaluator = Arc::new(WeightedEvaluator::load(WEIGHTS_FILE).unwrap_or_default());
+    let engine = HubEngine::new(evaluator)
+        .with_tablebase(Tablebase::load_static(TABLEBASE_FILE).ok())
+        .with_book(OpeningBook::load_static(BOOK_FILE).ok());
+    if let Err(e) = run(receiver, &mut io::stdout().lock(), engine) {
+        eprintln!("hub: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,71 @@
+// Usage: match <engine_a> <engine_b> [--games N] [--max-plies N] [--sprt elo0 elo1 alpha beta]
+// Engines are written like `ab:depth=5,weights=weights.txt`, `mcts:playouts=2000`,
+// `greedy` or `random`.
+use hw4::match_runner::{run_match, EngineSpec, MatchConfig, Sprt, SprtResult};
+
+fn usage() -> ! {
+    eprintln!("usage: match <engine_a> <engine_b> [--games N] [--max-plies N] [--sprt elo0 elo1 alpha beta]");
+    std::process::exit(2);
+}
+
+fn number<T: std::str::FromStr>(value: Option<&String>) -> T {
+    value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        usage();
+    }
+    let parse_spec = |text: &str| text.parse::<EngineSpec>().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+    let mut config = MatchConfig {
+        first: parse_spec(&args[0]),
+        second: parse_spec(&args[1]),
+        games: 100,
+        max_plies: 200,
+        sprt: None,
+    };
+    let mut rest = args[2..].iter();
+    while let Some(flag) = rest.next() {
+        match flag.as_str() {
+            "--games" => config.games = number(rest.next()),
+            "--max-plies" => config.max_plies = number(rest.next()),
+            "--sprt" => {
+                config.sprt = Some(Sprt {
+                    elo0: number(rest.next()),
+                    elo1: number(rest.next()),
+                    alpha: number(rest.next()),
+                    beta: number(rest.next()),
+                })
+            },
+            _ => usage(),
+        }
+    }
+
+    println!("{} vs {}", config.first, config.second);
+    let outcome = run_match(&config, |score| {
+        let llr = config.sprt.map(|sprt| {
+            let (lower, upper) = sprt.bounds();
+            format!(", llr {:.2} ({:.2}, {:.2})", score.llr(sprt.elo0, sprt.elo1), lower, upper)
+        }).unwrap_or_default();
+        eprintln!("game {}: {}{}", score.games(), score, llr);
+    });
+    match outcome {
+        Ok((score, decision)) => {
+            println!("final: {}", score);
+            match decision {
+                Some(SprtResult::AcceptH1) => println!("sprt: H1 accepted"),
+                Some(SprtResult::AcceptH0) => println!("sprt: H0 accepted"),
+                Some(SprtResult::Continue) => println!("sprt: inconclusive"),
+                None => (),
+            }
+        },
+        Err(e) => {
+            eprintln!("match failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Usage: play [--mode hot-seat|human-black|human-red|ai-vs-ai] [--difficulty <level>]
+//             [--fen <position>] [--max-plies N]
+// Plays in the terminal, for machines without a display or sessions over
+// SSH. Unset flags come from the GUI's settings file. Loads the weights and
+// tablebase files when they are present.
+use std::io::{self, BufRead, Write};
+
+use hw4::evaluator::{WeightedEvaluator, WEIGHTS_FILE};
+use hw4::game::{Game, GameResult};
+use hw4::mode::GameMode;
+use hw4::pdn::{from_fen, move_to_notation, parse_move, to_fen, START_FEN};
+use hw4::search::{best_move, SearchOptions};
+use hw4::settings::{Settings, SETTINGS_FILE};
+use hw4::tablebase::{Tablebase, TABLEBASE_FILE};
+use hw4::terminal::{render_board, Command, HELP};
+
+fn usage() -> ! {
+    eprintln!("usage: play [--mode hot-seat|human-black|human-red|ai-vs-ai] [--difficulty <level>] [--fen <position>] [--max-plies N]");
+    std::process::exit(2);
+}
+
+fn value<T: std::str::FromStr>(value: Option<&String>) -> T {
+    value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn side(current_turn: i32) -> &'static str {
+    if current_turn > 0 { "black" } else { "red" }
+}
+
+fn main() {
+    let mut settings = Settings::load(SETTINGS_FILE);
+    let mut start = from_fen(START_FEN).expect("valid start position");
+    // only games between engines are cut off; people can stop with quit
+    let mut max_plies = 200;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        match flag.as_str() {
+            "--mode" => settings.mode = value(rest.next()),
+            "--difficulty" => settings.difficulty = value(rest.next()),
+            "--fen" => start = rest.next().and_then(|fen| from_fen(fen)).unwrap_or_else(|| usage()),
+            "--max-plies" => max_plies = value(rest.next()),
+            _ => usage(),
+        }
+    }
+    if settings.mode == GameMode::Puzzle {
+        eprintln!("puzzles need the window; use hot-seat, human-black, human-red or ai-vs-ai");
+        std::process::exit(2);
+    }
+    let tablebase = Tablebase::load_static(TABLEBASE_FILE).ok();
+    let evaluator = WeightedEvaluator::load(WEIGHTS_FILE).unwrap_or_default();
+
+    let mode = settings.mode;
+    let mut game = Game::from_position(start);
+    let mut input = io::stdin().lock().lines();
+    println!("{} at {}, type help for commands", mode.label(), settings.difficulty);
+    println!("{}", render_board(&game.checkers));
+    loop {
+        let turn = game.checkers.current_turn;
+        if let Some(result) = game.result() {
+            let text = match result {
+                GameResult::BlackWon => "black wins",
+                GameResult::RedWon => "red wins",
+                GameResult::Draw => "draw",
+            };
+            println!("{}", text);
+            break;
+        }
+        if mode.is_ai(turn) {
+            if mode == GameMode::AiVsAi && game.history.len() >= max_plies {
+                println!("draw after {} plies", max_plies);
+                break;
+            }
+            let options = SearchOptions { tablebase, ..settings.search_options() };
+            match best_move(&game.checkers, &options, &evaluator) {
+                Ok((j, i, m)) => {
+                    game.play(j, i, &m).expect("search returns legal moves");
+                    println!("{} plays {}", side(turn), move_to_notation(j, i, &m));
+                    println!("{}", render_board(&game.checkers));
+                },
+                Err(e) => {
+                    eprintln!("{}", e);
+                    break;
+                },
+            }
+            continue;
+        }
+
+        print!("{}> ", side(turn));
+        io::stdout().flush().ok();
+        let Some(Ok(line)) = input.next() else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Command::parse(&line) {
+            Ok(Command::Move(text)) => match parse_move(&game.checkers, &text) {
+                Some((j, i, m)) => {
+                    game.play(j, i, &m).expect("parsed moves are legal");
+                    println!("{}", render_board(&game.checkers));
+                },
+                None => println!("{} is not a legal move here, type moves for the list", text),
+            },
+            Ok(Command::Undo) => match game.undo() {
+                Some(_) => {
+                    // against the engine, its reply goes too
+                    while mode.is_ai(game.checkers.current_turn) && game.undo().is_some() {}
+                    println!("{}", render_board(&game.checkers));
+                },
+                None => println!("nothing to undo"),
+            },
+            Ok(Command::Hint) => {
+                // hints are for learning, so they come without the level's deliberate mistakes
+                let options = SearchOptions { eval_noise: 0, blunder_chance: 0., multi_pv: 1, tablebase, ..settings.search_options() };
+                match best_move(&game.checkers, &options, &evaluator) {
+                    Ok((j, i, m)) => println!("hint: {}", move_to_notation(j, i, &m)),
+                    Err(e) => println!("{}", e),
+                }
+            },
+            Ok(Command::Moves) => {
+                let moves: Vec<String> = game.legal_moves().iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect();
+                println!("{}", moves.join(" "));
+            },
+            Ok(Command::Level(difficulty)) => {
+                settings.difficulty = difficulty;
+                println!("difficulty is now {}", difficulty);
+            },
+            Ok(Command::Fen) => println!("{}", to_fen(&game.checkers)),
+            Ok(Command::Help) => println!("{}", HELP),
+            Ok(Command::Quit) => break,
+            Err(e) => println!("{}", e),
+        }
+    }
+}
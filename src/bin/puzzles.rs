@@ -0,0 +1,55 @@
+// Usage:
+//   puzzles generate <games> [depth] [puzzles_file]
+//   puzzles import <games.pdn> [puzzles_file]
+// Scans self-play or imported games for shots and adds them to the puzzles
+// file, skipping positions it already holds.
+use std::collections::HashSet;
+
+use hw4::game::Game;
+use hw4::pdn::parse_pdn;
+use hw4::puzzle::{find_puzzles, load_puzzles, save_puzzles, PuzzleOptions, PUZZLES_FILE};
+use hw4::tuning::self_play_games;
+
+fn usage() -> ! {
+    eprintln!("usage: puzzles generate <games> [depth] [puzzles_file]");
+    eprintln!("       puzzles import <games.pdn> [puzzles_file]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let (games, path): (Vec<Game>, &str) = match args.get(1).map(String::as_str) {
+        Some("generate") => {
+            let count: usize = args.get(2).and_then(|a| a.parse().ok()).unwrap_or_else(|| usage());
+            let depth: usize = args.get(3).and_then(|a| a.parse().ok()).unwrap_or(6);
+            let games = self_play_games(count, depth, 0).into_iter().map(|(_, game)| game).collect();
+            (games, args.get(4).map_or(PUZZLES_FILE, String::as_str))
+        },
+        Some("import") => {
+            let input = args.get(2).unwrap_or_else(|| usage());
+            let text = match std::fs::read_to_string(input) {
+                Ok(text) => text,
+                Err(e) => {
+                    eprintln!("could not read {}: {}", input, e);
+                    std::process::exit(1);
+                }
+            };
+            let games = parse_pdn(&text).iter().map(|record| record.replay()).collect();
+            (games, args.get(3).map_or(PUZZLES_FILE, String::as_str))
+        },
+        _ => usage(),
+    };
+
+    let mut puzzles = load_puzzles(path).unwrap_or_default();
+    let mut seen: HashSet<u64> = puzzles.iter().map(|p| p.board.position_hash()).collect();
+    let before = puzzles.len();
+    let options = PuzzleOptions::default();
+    for game in &games {
+        puzzles.extend(find_puzzles(game, &options, &mut seen));
+    }
+    if let Err(e) = save_puzzles(path, &puzzles) {
+        eprintln!("could not write {}: {}", path, e);
+        std::process::exit(1);
+    }
+    println!("found {} new puzzles in {} games, {} in {}", puzzles.len() - before, games.len(), puzzles.len(), path);
+}
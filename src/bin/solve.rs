@@ -0,0 +1,28 @@
+// Usage: solve <fen> [node_budget] [max_plies]
+// Proves whether the side to move in a problem or endgame study wins, loses
+// or draws, and prints the line.
+use hw4::pdn::{from_fen, move_to_notation};
+use hw4::solver::{solve, SolverOptions};
+use hw4::tablebase::{Tablebase, TABLEBASE_FILE};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(board) = args.get(1).and_then(|fen| from_fen(fen)) else {
+        eprintln!("usage: solve <fen> [node_budget] [max_plies]");
+        std::process::exit(2);
+    };
+    let defaults = SolverOptions::default();
+    let options = SolverOptions {
+        node_budget: args.get(2).and_then(|a| a.parse().ok()).unwrap_or(defaults.node_budget),
+        max_plies: args.get(3).and_then(|a| a.parse().ok()).unwrap_or(defaults.max_plies),
+        tablebase: Tablebase::load_static(TABLEBASE_FILE).ok(),
+    };
+
+    let solution = solve(&board, &options);
+    let side = if board.current_turn > 0 { "black" } else { "red" };
+    println!("{} for {} to move ({} nodes)", solution.verdict, side, solution.nodes);
+    if !solution.line.is_empty() {
+        let line: Vec<String> = solution.line.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect();
+        println!("{}", line.join(" "));
+    }
+}
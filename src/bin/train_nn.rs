@@ -0,0 +1,38 @@
+// Usage: train_nn <positions_file> [epochs] [network_file]
+// Positions come from `tune generate`. Training continues from the network
+// file if it exists, otherwise it starts from random weights.
+use hw4::nn::{train, Network, TrainingOptions, DEFAULT_HIDDEN, NN_FILE};
+use hw4::tuning::load_positions;
+
+fn usage() -> ! {
+    eprintln!("usage: train_nn <positions_file> [epochs] [network_file]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let path = args.get(1).unwrap_or_else(|| usage());
+    let mut options = TrainingOptions::default();
+    if let Some(epochs) = args.get(2) {
+        options.epochs = epochs.parse().unwrap_or_else(|_| usage());
+    }
+    let output = args.get(3).map(String::as_str).unwrap_or(NN_FILE);
+
+    let positions = match load_positions(path) {
+        Ok(positions) => positions,
+        Err(e) => {
+            eprintln!("could not read {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+    let mut network = Network::load(output).unwrap_or_else(|_| Network::new(&DEFAULT_HIDDEN, options.seed));
+    println!("training on {} positions", positions.len());
+    train(&mut network, &positions, &options, |epoch, training_loss, validation_loss| {
+        println!("epoch {}: training loss {:.5} validation loss {:.5}", epoch, training_loss, validation_loss);
+    });
+    if let Err(e) = network.save(output) {
+        eprintln!("could not write {}: {}", output, e);
+        std::process::exit(1);
+    }
+    println!("wrote {}", output);
+}
@@ -0,0 +1,53 @@
+// Usage:
+//   tune generate <games> <depth> <positions_file>
+//   tune fit <positions_file> [weights_file]
+use hw4::evaluator::{Weights, WEIGHTS_FILE};
+use hw4::tuning::{load_positions, save_positions, self_play_positions, tune};
+
+fn usage() -> ! {
+    eprintln!("usage: tune generate <games> <depth> <positions_file>");
+    eprintln!("       tune fit <positions_file> [weights_file]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("generate") => {
+            let games: usize = args.get(2).and_then(|a| a.parse().ok()).unwrap_or_else(|| usage());
+            let depth: usize = args.get(3).and_then(|a| a.parse().ok()).unwrap_or_else(|| usage());
+            let path = args.get(4).unwrap_or_else(|| usage());
+            let positions = self_play_positions(games, depth, 0);
+            if let Err(e) = save_positions(path, &positions) {
+                eprintln!("could not write {}: {}", path, e);
+                std::process::exit(1);
+            }
+            println!("wrote {} positions to {}", positions.len(), path);
+        },
+        Some("fit") => {
+            let path = args.get(2).unwrap_or_else(|| usage());
+            let output = args.get(3).map(String::as_str).unwrap_or(WEIGHTS_FILE);
+            let positions = match load_positions(path) {
+                Ok(positions) => positions,
+                Err(e) => {
+                    eprintln!("could not read {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            };
+            let start = Weights::load(output).unwrap_or_default();
+            let weights = tune(&positions, start, 1000, |pass, scale, error, weights| {
+                if pass == 0 {
+                    println!("scale {:.3}, starting error {:.6}", scale, error);
+                } else {
+                    println!("pass {}: error {:.6} weights {:?}", pass, error, weights.0);
+                }
+            });
+            if let Err(e) = weights.save(output) {
+                eprintln!("could not write {}: {}", output, e);
+                std::process::exit(1);
+            }
+            println!("wrote {}", output);
+        },
+        _ => usage(),
+    }
+}
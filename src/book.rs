@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+
+use crate::checkers::{Checkers, Move};
+use crate::game::GameResult;
+use crate::pdn::{self, move_to_notation, parse_move};
+
+pub const BOOK_FILE: &str = "book.txt";
+
+// Known good moves per position, keyed by `Checkers::position_hash`. Moves are
+// kept in standard notation with a weight; heavier moves are played more often.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Vec<(String, u32)>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn add(&mut self, board: &Checkers, notation: &str, weight: u32) {
+        let moves = self.entries.entry(board.position_hash()).or_default();
+        match moves.iter_mut().find(|(n, _)| n == notation) {
+            Some((_, w)) => *w += weight,
+            None => moves.push((notation.to_string(), weight)),
+        }
+    }
+
+    pub fn moves(&self, board: &Checkers) -> Option<&[(String, u32)]> {
+        self.entries.get(&board.position_hash()).map(Vec::as_slice)
+    }
+
+    // Picks one of the book moves for this position at random, in proportion
+    // to the weights. Moves that are not legal here (hash collisions, stale
+    // books) are ignored.
+    pub fn pick(&self, board: &Checkers, rng: &mut impl Rng) -> Option<(usize, usize, Move)> {
+        let candidates: Vec<((usize, usize, Move), u32)> = self.moves(board)?
+            .iter()
+            .filter(|(_, weight)| *weight > 0)
+            .filter_map(|(notation, weight)| parse_move(board, notation).map(|m| (m, *weight)))
+            .collect();
+        let distribution = WeightedIndex::new(candidates.iter().map(|(_, weight)| *weight)).ok()?;
+        Some(candidates[distribution.sample(rng)].0.clone())
+    }
+
+    // One line per move: position hash in hex, move, weight.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+        let mut contents = String::new();
+        for key in keys {
+            for (notation, weight) in &self.entries[key] {
+                contents.push_str(&format!("{:016x} {} {}\n", key, notation, weight));
+            }
+        }
+        fs::write(path, contents)
+    }
+
+    // Blank lines and lines starting with '#' are skipped.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut book = Self::new();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match parts.as_slice() {
+                [hash, notation, weight] => u64::from_str_radix(hash, 16).ok().zip(weight.parse::<u32>().ok()).map(|(h, w)| (h, *notation, w)),
+                _ => None,
+            };
+            let (hash, notation, weight) = parsed.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("bad book line {}: {}", number + 1, line))
+            })?;
+            book.entries.entry(hash).or_default().push((notation.to_string(), weight));
+        }
+        Ok(book)
+    }
+
+    // Loads a book that stays for the rest of the program, for the search
+    // options to refer to.
+    pub fn load_static(path: impl AsRef<Path>) -> io::Result<&'static Self> {
+        Self::load(path).map(|book| &*Box::leak(Box::new(book)))
+    }
+
+    // Builds a book from the first `max_plies` plies of every game in a PDN
+    // collection. Each game adds one to the weight of every move it played,
+    // and one more for moves made by the side that went on to win.
+    pub fn from_pdn(text: &str, max_plies: usize) -> Self {
+        let mut book = Self::new();
+        for pdn_game in pdn::parse_pdn(text) {
+            let game = pdn_game.replay();
+            let mut board = game.start;
+            for (j, i, m) in game.history.iter().take(max_plies) {
+                let mover = board.current_turn;
+                let won = match pdn_game.result {
+                    Some(GameResult::BlackWon) => mover > 0,
+                    Some(GameResult::RedWon) => mover < 0,
+                    _ => false,
+                };
+                book.add(&board, &move_to_notation(*j, *i, m), if won { 2 } else { 1 });
+                board.make_move_from_enum(*j, *i, m).expect("replayed move failed");
+            }
+        }
+        book
+    }
+}
@@ -4,12 +4,12 @@
 // BLACK KING: 3
 
 
+use std::sync::atomic::AtomicBool;
 use thiserror::Error;
-use std::sync::Mutex;
-use std::sync::Arc;
-use rayon::prelude::*;
+use crate::evaluator::{Evaluator, MaterialEvaluator};
+use crate::search::{self, SearchOptions, SearchStats};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Move {
     UpLeft(usize),
     DownLeft(usize), 
@@ -18,6 +18,36 @@ pub enum Move {
     Sequence(Vec<Move>)
 }
 
+impl Move {
+    // Squares visited by this move starting from (j, i), including the start.
+    pub fn path(&self, j: usize, i: usize) -> Vec<(usize, usize)> {
+        let mut path = vec![(j, i)];
+        let steps: Vec<&Move> = match self {
+            Move::Sequence(sequence) => sequence.iter().collect(),
+            single => vec![single],
+        };
+        for step in steps {
+            let (current_j, current_i) = path[path.len() - 1];
+            let next = match step {
+                Move::UpLeft(size) => (current_j - size, current_i - size),
+                Move::UpRight(size) => (current_j - size, current_i + size),
+                Move::DownLeft(size) => (current_j + size, current_i - size),
+                Move::DownRight(size) => (current_j + size, current_i + size),
+                Move::Sequence(_) => panic!("how got composite in composite"),
+            };
+            path.push(next);
+        }
+        path
+    }
+
+    pub fn is_jump(&self) -> bool {
+        match self {
+            Move::Sequence(_) => true,
+            Move::UpLeft(size) | Move::UpRight(size) | Move::DownLeft(size) | Move::DownRight(size) => *size == 2,
+        }
+    }
+}
+
 
 #[derive(Error, Debug, Clone, Copy)]
 pub enum CheckersError {
@@ -29,6 +59,13 @@ pub enum CheckersError {
     GameOver
 }
 
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 #[derive(Clone, Copy)]
 pub struct Checkers {
     pub board_state: [[i32; 8]; 8],
@@ -69,13 +106,6 @@ impl Checkers {
             }
         }
 
-        for j in 0..8 {
-            let mut string = String::new();
-            for i in 0..8 {
-                string.push_str(board_state[j][i].to_string().as_str());
-            }
-            println!("{}", string);
-        }
         let checkers = Self {
             board_state,
             current_turn: Self::BLACK_PIECE,
@@ -86,6 +116,35 @@ impl Checkers {
         Ok(checkers)
     }
 
+    // A position with the given pieces and side to move, between moves.
+    pub fn from_position(board_state: [[i32; 8]; 8], current_turn: i32) -> Self {
+        Self {
+            board_state,
+            current_turn,
+            required_square: None,
+            game_over: false,
+            calculating: false
+        }
+    }
+
+    // Zobrist-style hash of the pieces and the side to move. Opening books are
+    // keyed by it, so it must stay stable.
+    pub fn position_hash(&self) -> u64 {
+        let mut hash: u64 = if self.current_turn > 0 { 0x9e37_79b9_7f4a_7c15 } else { 0 };
+        for (j, row) in self.board_state.iter().enumerate() {
+            for (i, &piece) in row.iter().enumerate() {
+                if piece != 0 {
+                    hash ^= splitmix64(((j * 8 + i) as u64) << 3 | (piece + 3) as u64);
+                }
+            }
+        }
+        hash
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.board_state.iter().flatten().filter(|&&piece| piece != 0).count()
+    }
+
     pub fn is_game_over(&self) -> bool {
         self.black_won() || self.red_won()
     }
@@ -399,26 +458,25 @@ impl Checkers {
         Ok(())
     }
 
-    pub fn make_move_then_ai(&mut self, move_from_j: usize, move_from_i: usize, move_to_j: usize, move_to_i: usize) -> Result<(), CheckersError> {
+    pub fn make_move_then_ai(&mut self, move_from_j: usize, move_from_i: usize, move_to_j: usize, move_to_i: usize, options: &SearchOptions, evaluator: &dyn Evaluator) -> Result<(), CheckersError> {
         let stored_state = self.current_turn;
         self.make_move(move_from_j, move_from_i, move_to_j, move_to_i).expect("move from enum has failed");
         if stored_state > 0 && self.current_turn > 0 || stored_state < 0 && self.current_turn < 0 {
             Ok(())
         } else {
-            match self.get_best_move(7, self.current_turn) {
-                Ok((j, i, m)) => {
-                    println!("best move for {} is {}, {}, {:?}", self.current_turn, j, i, m);
-                    if self.current_turn < 0 {
-                        println!("got here");
-                        self.make_move_from_enum(j, i, &m).expect("move from enum has failed");
-                    }
-                    Ok(())
-                },
-                Err(e) => Err(e)
-            } 
+            self.make_ai_move(options, evaluator)
         }
 
     }
+
+    // Plays the engine's move for whichever side is to move, so the engine
+    // can take either color and open the game as black.
+    pub fn make_ai_move(&mut self, options: &SearchOptions, evaluator: &dyn Evaluator) -> Result<(), CheckersError> {
+        let (j, i, m) = self.get_best_move_with_options(options, evaluator)?;
+        self.make_move_from_enum(j, i, &m)?;
+        Ok(())
+    }
+
     pub fn make_move_from_enum (&mut self, move_from_j: usize, move_from_i: usize, move_to_make: &Move) -> Result<(usize, usize), CheckersError> {
         let mut current_j = move_from_j;
         let mut current_i = move_from_i;
@@ -447,7 +505,7 @@ impl Checkers {
                             current_j += size;
                         }, 
                         _ => {
-                            println!("{:?}", move_to_make);
+                            eprintln!("{:?}", move_to_make);
                             panic!("how got composite in composite")
                         }
                     }
@@ -455,12 +513,12 @@ impl Checkers {
             }, 
             Move::UpLeft(size) => {
                 if *size == 1 && !self.can_move_up_left(current_j, current_i){
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 } else if *size == 2 && !self.can_jump_up_left(current_j, current_i) {
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 }
                 match self.make_move(current_j, current_i, current_j - size, current_i - size) {
@@ -472,12 +530,12 @@ impl Checkers {
             }, 
             Move::UpRight(size) => {
                 if *size == 1 && !self.can_move_up_right(current_j, current_i){
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 } else if *size == 2 && !self.can_jump_up_right(current_j, current_i) {
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 }
                 
@@ -490,12 +548,12 @@ impl Checkers {
             }, 
             Move::DownLeft(size) => {
                 if *size == 1 && !self.can_move_down_left(current_j, current_i){
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 } else if *size == 2 && !self.can_jump_down_left(current_j, current_i) {
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 }
                 match self.make_move(current_j, current_i, current_j + size, current_i - size) {
@@ -507,12 +565,12 @@ impl Checkers {
             },
             Move::DownRight(size) => {
                 if *size == 1 && !self.can_move_down_right(current_j, current_i){
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 } else if *size == 2 && !self.can_jump_down_right(current_j, current_i) {
-                    println!("{:?}", self.board_state);
-                    println!("{:?}, ({}, {})", move_to_make, current_j, current_i);
+                    eprintln!("{:?}", self.board_state);
+                    eprintln!("{:?}, ({}, {})", move_to_make, current_j, current_i);
                     panic!("how");
                 }
                 match self.make_move(current_j, current_i, current_j + size, current_i + size) {
@@ -528,145 +586,57 @@ impl Checkers {
     }
 
     pub fn get_best_move(&mut self, max_depth: usize, _team: i32) -> Result<(usize, usize, Move), CheckersError> {
-        let default: i32;
-        if self.current_turn < 0 {
-            default = std::i32::MAX;
-        } else {
-            default = std::i32::MIN;
-        }
-        let best_move = Arc::new(Mutex::new(None));
-        let best_move_from = Arc::new(Mutex::new(None));
-        let best_move_score = Arc::new(Mutex::new(default));
-        
-        (0..8).into_par_iter().for_each(|j| {
-            (0..8).into_par_iter().for_each(|i| {
-                if self.board_state[j][i] > 0 && self.current_turn > 0 {
-                    if let Some(moves_to_make) = self.get_all_possible_moves(j, i) {
-                        //println!("3 {}, {}", j, i);
-                        //println!("{:?}", moves_to_make);
-                        for m in moves_to_make {
-                            let mut new_board = *self;
-                            new_board.make_move_from_enum(j, i, &m).expect("move from enum failed");
-                            let score = new_board.minimax(1, max_depth, false, std::i32::MIN, std::i32::MAX);
-                            println!("score from {:?} from ({}, {}) is {}", m, j, i, score);
-                            let mut best_score = best_move_score.lock().expect("how no value");
-                            if score > *best_score {
-                                println!("updating");
-                                *best_score = score;
-                                *best_move_from.lock().expect("no best move location?") = Some((j, i));
-                                *best_move.lock().expect("no best move?") = Some(m);
-                            }
-                        }
-                    }
-                    
-                } else if self.board_state[j][i] < 0 && self.current_turn < 0 {
-                    if let Some(moves_to_make) = self.get_all_possible_moves(j, i) {
-                        //println!("3 {}, {}", j, i);
-                        //println!("{:?}", moves_to_make);
-                        for m in moves_to_make {
-                            let mut new_board = *self;
-                            new_board.make_move_from_enum(j, i, &m).expect("move from enum failed");
-                            //println!("hello");
-                            let score = new_board.minimax(1, max_depth, true, std::i32::MIN, std::i32::MAX);
-                            println!("score from {:?} from ({}, {}) is {}", m, j, i, score);
-                            let mut best_score = best_move_score.lock().expect("how no value");
-                            if score < *best_score {
-                                println!("updating");
-                                *best_score = score;
-                                *best_move_from.lock().expect("no best move location?") = Some((j, i));
-                                *best_move.lock().expect("no best move?") = Some(m);
-                            }
-                        }
-                    }
-                }
-            });
-        });
-        
-        let move_option = best_move.lock().expect("why isn't there mutexguard");
-        match move_option.as_ref() {
-            Some(mov) => {
-                match best_move_from.lock().expect("why no guard on move_from").as_ref() {
-                    Some((j, i)) => {
-                        Ok((*j, *i, mov.clone()))
-                    }, 
-                    None => Err(CheckersError::GameOver)
-                }
-            },
-            None => Err(CheckersError::GameOver)
-        }
-
-    }
-
-    pub fn minimax(&mut self, current_depth: usize, max_depth: usize, is_maximizing_player: bool, mut alpha: i32, mut beta: i32) -> i32 {
-        //println!("received");
-        if current_depth == max_depth {
-            
-            //println!("evaluating... {}", current);
-            self.evaluate_board()
-        } else if is_maximizing_player {
-            //println!("maximizing depth {}", current_depth);
-            let mut best_val: i32 = std::i32::MIN;
-            for j in 0..8 {
-                for i in 0..8 {
-                    if self.board_state[j][i] > 0 {
-                        if let Some(vec) = self.get_all_possible_moves(j, i) {
-                            for m in vec {
-                                let mut copy = *self;
-                                copy.make_move_from_enum(j, i, &m).expect("move from enum function failed");
-                                let value = copy.minimax(current_depth + 1, max_depth, false, alpha, beta);
-                                best_val = std::cmp::max(best_val, value);
-                                alpha = std::cmp::max(alpha, best_val);
-                                if beta <= alpha {
-                                    break;
-                                }
-                            }
-                        } 
-                    }
-                }
-            }
-            best_val
-        } else {
-            //println!("not maximizing depth {}", current_depth);
-            let mut best_val: i32 = std::i32::MAX;
-            for j in 0..8 {
-                for i in 0..8 {
-                    if self.board_state[j][i] < 0 {
-                        if let Some(vec) = self.get_all_possible_moves(j, i) {
-                            for m in vec {
-                                let mut copy = *self;
-                                copy.make_move_from_enum(j, i, &m).expect("move_from_enum_function failed");
-                                let value = copy.minimax(current_depth + 1, max_depth, true, alpha, beta);
-                                best_val = std::cmp::min(best_val, value);
-                                beta = std::cmp::min(beta, best_val);
-                                if beta <= alpha {
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            best_val
-        }
+        self.get_best_move_with(max_depth, &MaterialEvaluator)
+    }
+
+    pub fn get_best_move_with(&mut self, max_depth: usize, evaluator: &dyn Evaluator) -> Result<(usize, usize, Move), CheckersError> {
+        self.get_best_move_with_options(&SearchOptions::fixed_depth(max_depth), evaluator)
+    }
+
+    pub fn get_best_move_with_options(&mut self, options: &SearchOptions, evaluator: &dyn Evaluator) -> Result<(usize, usize, Move), CheckersError> {
+        self.get_best_move_cancellable(options, evaluator, &AtomicBool::new(false))
+    }
 
+    pub fn get_best_move_cancellable(&mut self, options: &SearchOptions, evaluator: &dyn Evaluator, cancel: &AtomicBool) -> Result<(usize, usize, Move), CheckersError> {
+        self.get_best_move_reporting(options, evaluator, cancel, &mut |_| ())
+    }
 
+    // `report` gets the search statistics after every finished iteration.
+    // Book moves are played without searching, so nothing is reported.
+    pub fn get_best_move_reporting(&mut self, options: &SearchOptions, evaluator: &dyn Evaluator, cancel: &AtomicBool, report: &mut dyn FnMut(&SearchStats)) -> Result<(usize, usize, Move), CheckersError> {
+        if let Some(book_move) = options.book.and_then(|book| book.pick(self, &mut options.rng(self))) {
+            return Ok(book_move);
+        }
+        search::best_move_with_stats(self, options, evaluator, cancel, report).0
     }
 
     pub fn evaluate_board(&mut self) -> i32 {
-        let mut score = 0;
         if self.red_won() {
-            println!("red won");
-            return std::i32::MIN;
+            return i32::MIN;
         } else if self.black_won() {
-            println!("black won");
-            return std::i32::MAX;
-        }
-        score += self.count_material() * 5;
-        score += self.count_vulnerable_red() ;
-        //println!("vulnerable red: {}", self.count_vulnerable_red());
-        score -= self.count_vulnerable_black();
-        //println!("vulnerable black: {}", self.count_vulnerable_black());
-        score
+            return i32::MAX;
+        }
+        MaterialEvaluator.evaluate(self)
+    }
+
+    // Every move available to the side whose turn it is, as (from_j, from_i, move).
+    // Partway through a multi-jump that is only the rest of the jump.
+    pub fn get_all_moves(&self) -> Vec<(usize, usize, Move)> {
+        if let Some((j, i)) = self.required_square {
+            let jumps = self.get_all_possible_moves(j, i).unwrap_or_default().into_iter().filter(Move::is_jump);
+            return jumps.map(|m| (j, i, m)).collect();
+        }
+        let mut moves = Vec::new();
+        for (j, row) in self.board_state.iter().enumerate() {
+            for (i, &piece) in row.iter().enumerate() {
+                if piece * self.current_turn > 0 {
+                    if let Some(vec) = self.get_all_possible_moves(j, i) {
+                        moves.extend(vec.into_iter().map(|m| (j, i, m)));
+                    }
+                }
+            }
+        }
+        moves
     }
 
     pub fn count_material(&self) -> i32 {
@@ -0,0 +1,79 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::search::SearchOptions;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Medium,
+    #[default]
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Beginner,
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    // Hard searches like the old fixed 7 ply search, within a time budget;
+    // the easier levels search less, add noise to their scores, pick at
+    // random among their few best moves and make deliberate mistakes. The
+    // thread count is the caller's, usually from the settings.
+    pub fn search_options(&self, threads: usize) -> SearchOptions {
+        let (max_depth, millis, eval_noise, multi_pv, pv_margin, blunder_chance) = match self {
+            Difficulty::Beginner => (2, 250, 15, 4, 15, 0.3),
+            Difficulty::Easy => (3, 500, 8, 3, 8, 0.15),
+            Difficulty::Medium => (5, 1000, 3, 2, 3, 0.05),
+            Difficulty::Hard => (7, 2000, 0, 1, 0, 0.),
+            Difficulty::Expert => (12, 5000, 0, 1, 0, 0.),
+        };
+        SearchOptions {
+            max_depth,
+            time_budget: Some(Duration::from_millis(millis)),
+            eval_noise,
+            blunder_chance,
+            multi_pv,
+            pv_margin,
+            threads,
+            deterministic_seed: None,
+            tablebase: None,
+            book: None,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .iter()
+            .find(|d| d.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| format!("unknown difficulty {}", s.trim()))
+    }
+}
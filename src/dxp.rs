@@ -0,0 +1,474 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::checkers::{Checkers, CheckersError, Move};
+use crate::game::{Game, GameResult};
+use crate::pdn::{from_fen, square_coords, square_number, START_FEN};
+use crate::player::Player;
+use crate::search::{default_threads, SearchOptions};
+
+pub const DXP_VERSION: &str = "01";
+pub const DEFAULT_PORT: u16 = 27531;
+const NAME_LENGTH: usize = 32;
+// Games still going after this many plies are ended as draws.
+pub const DEFAULT_MAX_PLIES: usize = 200;
+
+#[derive(Error, Debug)]
+pub enum DxpError {
+    #[error("connection error: {0}")]
+    Io(#[from] io::Error),
+    #[error("connection closed")]
+    Closed,
+    #[error("bad message {0:?}")]
+    BadMessage(String),
+    #[error("unexpected message {0:?}")]
+    Unexpected(String),
+    #[error("illegal move {0}")]
+    IllegalMove(String),
+    #[error("game request refused with code {0}")]
+    Refused(char),
+    #[error("game ended without a result")]
+    Aborted,
+    #[error("{0}")]
+    Checkers(#[from] CheckersError),
+}
+
+// Why a game ended, from the point of view of the side sending GAMEEND.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndReason {
+    Unknown,
+    Lose,
+    Draw,
+    Win,
+}
+
+impl EndReason {
+    fn code(&self) -> char {
+        match self {
+            EndReason::Unknown => '0',
+            EndReason::Lose => '1',
+            EndReason::Draw => '2',
+            EndReason::Win => '3',
+        }
+    }
+
+    fn from_code(code: char) -> Option<Self> {
+        match code {
+            '0' => Some(EndReason::Unknown),
+            '1' => Some(EndReason::Lose),
+            '2' => Some(EndReason::Draw),
+            '3' => Some(EndReason::Win),
+            _ => None,
+        }
+    }
+
+    // The same ending seen from the other side.
+    fn opposite(&self) -> Self {
+        match self {
+            EndReason::Lose => EndReason::Win,
+            EndReason::Win => EndReason::Lose,
+            other => *other,
+        }
+    }
+
+    // The result when `color` (the sign of `current_turn`) gives this reason.
+    fn result_for(&self, color: i32) -> Option<GameResult> {
+        match (self, color > 0) {
+            (EndReason::Draw, _) => Some(GameResult::Draw),
+            (EndReason::Win, true) | (EndReason::Lose, false) => Some(GameResult::BlackWon),
+            (EndReason::Win, false) | (EndReason::Lose, true) => Some(GameResult::RedWon),
+            (EndReason::Unknown, _) => None,
+        }
+    }
+}
+
+// One DXP message. Colors are the signs of `current_turn`: DXP's white
+// (`W`) is red and its black (`Z`) is black. Squares use PDN numbering.
+#[derive(Clone)]
+pub enum DxpMessage {
+    Chat(String),
+    GameRequest {
+        name: String,
+        follower_color: i32,
+        // Thinking time in minutes for `moves` moves.
+        minutes: u32,
+        moves: u32,
+        // None for the standard starting position.
+        position: Option<Box<Checkers>>,
+    },
+    GameAccept {
+        name: String,
+        // '0' accepts; anything else refuses.
+        code: char,
+    },
+    Move {
+        // Seconds spent on the move.
+        seconds: u32,
+        from: usize,
+        to: usize,
+        captured: Vec<usize>,
+    },
+    GameEnd {
+        reason: EndReason,
+        // True when the sender does not want another game.
+        stop: bool,
+    },
+    BackRequest {
+        // The move number to return to and the color to move there.
+        move_number: u32,
+        color: i32,
+    },
+    BackAccept {
+        code: char,
+    },
+}
+
+fn color_char(color: i32) -> char {
+    if color > 0 { 'Z' } else { 'W' }
+}
+
+fn parse_color(c: char) -> Option<i32> {
+    match c {
+        'Z' => Some(1),
+        'W' => Some(-1),
+        _ => None,
+    }
+}
+
+fn encode_board(board: &Checkers) -> String {
+    let mut text = String::new();
+    text.push(color_char(board.current_turn));
+    for number in 1..=32 {
+        let (j, i) = square_coords(number).expect("valid square");
+        text.push(match board.board_state[j][i] {
+            1 => 'z',
+            3 => 'Z',
+            -1 => 'w',
+            -3 => 'W',
+            _ => 'e',
+        });
+    }
+    text
+}
+
+fn decode_board(text: &str) -> Option<Checkers> {
+    let mut chars = text.chars();
+    let current_turn = parse_color(chars.next()?)?;
+    let mut board_state = [[0; 8]; 8];
+    for number in 1..=32 {
+        let (j, i) = square_coords(number)?;
+        board_state[j][i] = match chars.next()? {
+            'z' => 1,
+            'Z' => 3,
+            'w' => -1,
+            'W' => -3,
+            'e' => 0,
+            _ => return None,
+        };
+    }
+    Some(Checkers::from_position(board_state, current_turn))
+}
+
+impl DxpMessage {
+    // The message as sent, without the terminating NUL.
+    pub fn encode(&self) -> String {
+        match self {
+            DxpMessage::Chat(text) => format!("C{}", text),
+            DxpMessage::GameRequest { name, follower_color, minutes, moves, position } => {
+                let start = match position {
+                    Some(board) => format!("B{}", encode_board(board)),
+                    None => "A".to_string(),
+                };
+                format!("R{}{:<32.32}{}{:03}{:03}{}", DXP_VERSION, name, color_char(*follower_color), minutes, moves, start)
+            },
+            DxpMessage::GameAccept { name, code } => format!("A{:<32.32}{}", name, code),
+            DxpMessage::Move { seconds, from, to, captured } => {
+                let squares: String = captured.iter().map(|square| format!("{:02}", square)).collect();
+                format!("M{:04}{:02}{:02}{:02}{}", seconds, from, to, captured.len(), squares)
+            },
+            DxpMessage::GameEnd { reason, stop } => format!("E{}{}", reason.code(), if *stop { '1' } else { '0' }),
+            DxpMessage::BackRequest { move_number, color } => format!("B{:03}{}", move_number, color_char(*color)),
+            DxpMessage::BackAccept { code } => format!("K{}", code),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, DxpError> {
+        let bad = || DxpError::BadMessage(text.to_string());
+        let number = |range: std::ops::Range<usize>| text.get(range).and_then(|digits| digits.trim().parse::<u32>().ok()).ok_or_else(bad);
+        let char_at = |index: usize| text.get(index..).and_then(|rest| rest.chars().next()).ok_or_else(bad);
+        if !text.is_ascii() {
+            return Err(bad());
+        }
+        match text.chars().next() {
+            Some('C') => Ok(DxpMessage::Chat(text[1..].to_string())),
+            Some('R') => {
+                let name = text.get(3..3 + NAME_LENGTH).ok_or_else(bad)?.trim_end().to_string();
+                let follower_color = parse_color(char_at(35)?).ok_or_else(bad)?;
+                let position = match char_at(42)? {
+                    'A' => None,
+                    'B' => Some(Box::new(text.get(43..).and_then(decode_board).ok_or_else(bad)?)),
+                    _ => return Err(bad()),
+                };
+                Ok(DxpMessage::GameRequest { name, follower_color, minutes: number(36..39)?, moves: number(39..42)?, position })
+            },
+            Some('A') => Ok(DxpMessage::GameAccept {
+                name: text.get(1..1 + NAME_LENGTH).ok_or_else(bad)?.trim_end().to_string(),
+                code: char_at(1 + NAME_LENGTH)?,
+            }),
+            Some('M') => {
+                let count = number(9..11)? as usize;
+                let captured = (0..count).map(|n| number(11 + 2 * n..13 + 2 * n).map(|s| s as usize)).collect::<Result<_, _>>()?;
+                Ok(DxpMessage::Move { seconds: number(1..5)?, from: number(5..7)? as usize, to: number(7..9)? as usize, captured })
+            },
+            Some('E') => Ok(DxpMessage::GameEnd {
+                reason: EndReason::from_code(char_at(1)?).ok_or_else(bad)?,
+                stop: char_at(2)? == '1',
+            }),
+            Some('B') => Ok(DxpMessage::BackRequest {
+                move_number: number(1..4)?,
+                color: parse_color(char_at(4)?).ok_or_else(bad)?,
+            }),
+            Some('K') => Ok(DxpMessage::BackAccept { code: char_at(1)? }),
+            _ => Err(bad()),
+        }
+    }
+}
+
+// A move as DXP sends it: start and end squares and the captured squares.
+pub fn move_to_dxp(j: usize, i: usize, m: &Move) -> (usize, usize, Vec<usize>) {
+    let path = m.path(j, i);
+    let captured = if m.is_jump() {
+        path.windows(2).map(|step| square_number((step[0].0 + step[1].0) / 2, (step[0].1 + step[1].1) / 2)).collect()
+    } else {
+        Vec::new()
+    };
+    let (first, last) = (path[0], path[path.len() - 1]);
+    (square_number(first.0, first.1), square_number(last.0, last.1), captured)
+}
+
+// The legal move with these squares. Captured squares may come in any order.
+pub fn find_dxp_move(board: &Checkers, from: usize, to: usize, captured: &[usize]) -> Option<(usize, usize, Move)> {
+    let mut wanted = captured.to_vec();
+    wanted.sort_unstable();
+    board.get_all_moves().into_iter().find(|(j, i, m)| {
+        let (move_from, move_to, mut move_captured) = move_to_dxp(*j, *i, m);
+        move_captured.sort_unstable();
+        move_from == from && move_to == to && move_captured == wanted
+    })
+}
+
+// NUL-terminated messages over one TCP stream.
+pub struct DxpConnection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl DxpConnection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        Ok(Self { writer: stream.try_clone()?, reader: BufReader::new(stream) })
+    }
+
+    pub fn send(&mut self, message: &DxpMessage) -> Result<(), DxpError> {
+        let mut bytes = message.encode().into_bytes();
+        bytes.push(0);
+        self.writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    // The next message other than chat.
+    pub fn receive(&mut self) -> Result<DxpMessage, DxpError> {
+        loop {
+            let mut bytes = Vec::new();
+            if self.reader.read_until(0, &mut bytes)? == 0 {
+                return Err(DxpError::Closed);
+            }
+            if bytes.last() == Some(&0) {
+                bytes.pop();
+            }
+            let text = String::from_utf8_lossy(&bytes).into_owned();
+            match DxpMessage::parse(&text)? {
+                DxpMessage::Chat(_) => continue,
+                message => return Ok(message),
+            }
+        }
+    }
+}
+
+// What the opponent did while we waited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DxpEvent {
+    Moved(usize, usize, Move),
+    // The opponent took moves back; the game has been rewound.
+    TookBack,
+    Ended(Option<GameResult>),
+}
+
+// One game over DXP. The initiator sends the game request and the follower
+// accepts it; after that both sides are the same.
+pub struct DxpSession {
+    connection: DxpConnection,
+    pub game: Game,
+    pub color: i32,
+    pub opponent: String,
+    pub minutes: u32,
+    pub moves: u32,
+    pub max_plies: usize,
+}
+
+impl DxpSession {
+    // Asks for a game in which we play `color`, from `position` or the
+    // standard start.
+    pub fn initiate(stream: TcpStream, name: &str, color: i32, minutes: u32, moves: u32, position: Option<Checkers>) -> Result<Self, DxpError> {
+        let mut connection = DxpConnection::new(stream)?;
+        connection.send(&DxpMessage::GameRequest { name: name.to_string(), follower_color: -color, minutes, moves, position: position.map(Box::new) })?;
+        match connection.receive()? {
+            DxpMessage::GameAccept { name: opponent, code: '0' } => Ok(Self::new(connection, color, opponent, minutes, moves, position)),
+            DxpMessage::GameAccept { code, .. } => Err(DxpError::Refused(code)),
+            other => Err(DxpError::Unexpected(other.encode())),
+        }
+    }
+
+    // Waits for a game request and accepts it.
+    pub fn accept(stream: TcpStream, name: &str) -> Result<Self, DxpError> {
+        let mut connection = DxpConnection::new(stream)?;
+        match connection.receive()? {
+            DxpMessage::GameRequest { name: opponent, follower_color, minutes, moves, position } => {
+                connection.send(&DxpMessage::GameAccept { name: name.to_string(), code: '0' })?;
+                Ok(Self::new(connection, follower_color, opponent, minutes, moves, position.map(|board| *board)))
+            },
+            other => Err(DxpError::Unexpected(other.encode())),
+        }
+    }
+
+    fn new(connection: DxpConnection, color: i32, opponent: String, minutes: u32, moves: u32, position: Option<Checkers>) -> Self {
+        let start = position.unwrap_or_else(|| from_fen(START_FEN).expect("valid start position"));
+        Self { connection, game: Game::from_position(start), color, opponent, minutes, moves, max_plies: DEFAULT_MAX_PLIES }
+    }
+
+    // Thinking time per move under the agreed time control.
+    pub fn time_per_move(&self) -> Duration {
+        Duration::from_secs_f64(self.minutes as f64 * 60. / self.moves.max(1) as f64)
+    }
+
+    // A search of at most `max_depth` that keeps to the time per move.
+    pub fn search_options(&self, max_depth: usize) -> SearchOptions {
+        SearchOptions {
+            time_budget: Some(self.time_per_move()),
+            threads: default_threads(),
+            ..SearchOptions::fixed_depth(max_depth)
+        }
+    }
+
+    pub fn our_turn(&self) -> bool {
+        self.game.checkers.current_turn.signum() == self.color
+    }
+
+    // Plays our move and sends it.
+    pub fn send_move(&mut self, j: usize, i: usize, m: &Move, elapsed: Duration) -> Result<(), DxpError> {
+        self.game.play(j, i, m)?;
+        let (from, to, captured) = move_to_dxp(j, i, m);
+        self.connection.send(&DxpMessage::Move { seconds: elapsed.as_secs() as u32, from, to, captured })
+    }
+
+    // Waits for the opponent's move, answering take-back requests and the
+    // end of the game on the way.
+    pub fn receive(&mut self) -> Result<DxpEvent, DxpError> {
+        match self.connection.receive()? {
+            DxpMessage::Move { from, to, captured, .. } => {
+                let squares = format!("{}-{} x {:?}", from, to, captured);
+                let (j, i, m) = find_dxp_move(&self.game.checkers, from, to, &captured).ok_or(DxpError::IllegalMove(squares))?;
+                self.game.play(j, i, &m)?;
+                Ok(DxpEvent::Moved(j, i, m))
+            },
+            DxpMessage::BackRequest { move_number, color } => {
+                let accepted = self.take_back(move_number, color);
+                self.connection.send(&DxpMessage::BackAccept { code: if accepted { '0' } else { '2' } })?;
+                Ok(DxpEvent::TookBack)
+            },
+            DxpMessage::GameEnd { reason, .. } => {
+                self.connection.send(&DxpMessage::GameEnd { reason: reason.opposite(), stop: false })?;
+                Ok(DxpEvent::Ended(reason.result_for(-self.color)))
+            },
+            other => Err(DxpError::Unexpected(other.encode())),
+        }
+    }
+
+    // The ply of the game at which `color` is to play move `move_number`.
+    fn ply_of(&self, move_number: u32, color: i32) -> Option<usize> {
+        let first = self.game.start.current_turn.signum();
+        Some((move_number.checked_sub(1)? as usize) * 2 + usize::from(color != first))
+    }
+
+    // Rewinds the game to move `move_number` with `color` to move.
+    fn take_back(&mut self, move_number: u32, color: i32) -> bool {
+        let Some(ply) = self.ply_of(move_number, color) else {
+            return false;
+        };
+        if ply > self.game.history.len() {
+            return false;
+        }
+        let mut rewound = Game::from_position(self.game.start);
+        for (j, i, m) in &self.game.history[..ply] {
+            if rewound.play(*j, *i, m).is_err() {
+                return false;
+            }
+        }
+        if rewound.checkers.current_turn.signum() != color {
+            return false;
+        }
+        self.game = rewound;
+        true
+    }
+
+    // Asks the opponent to go back to `move_number` with `color` to move.
+    // Returns whether they agreed; the game is rewound if so.
+    pub fn request_back(&mut self, move_number: u32, color: i32) -> Result<bool, DxpError> {
+        self.connection.send(&DxpMessage::BackRequest { move_number, color })?;
+        match self.connection.receive()? {
+            DxpMessage::BackAccept { code: '0' } => Ok(self.take_back(move_number, color)),
+            DxpMessage::BackAccept { .. } => Ok(false),
+            other => Err(DxpError::Unexpected(other.encode())),
+        }
+    }
+
+    // Ends the game with our view of the result and waits for the
+    // opponent's GAMEEND in reply.
+    pub fn end(&mut self, reason: EndReason) -> Result<Option<GameResult>, DxpError> {
+        self.connection.send(&DxpMessage::GameEnd { reason, stop: false })?;
+        loop {
+            match self.connection.receive()? {
+                DxpMessage::GameEnd { .. } => return Ok(reason.result_for(self.color)),
+                // a move crossing our GAMEEND on the wire no longer counts
+                DxpMessage::Move { .. } => continue,
+                other => return Err(DxpError::Unexpected(other.encode())),
+            }
+        }
+    }
+
+    // Plays the game out with `player` making our moves. Whoever finds the
+    // side to move without moves, or the ply limit reached, ends the game.
+    pub fn play(&mut self, player: &mut dyn Player) -> Result<GameResult, DxpError> {
+        loop {
+            let ended = match self.game.result() {
+                Some(GameResult::Draw) => Some(EndReason::Draw),
+                Some(result) => Some(if (result == GameResult::BlackWon) == (self.color > 0) { EndReason::Win } else { EndReason::Lose }),
+                None if self.game.history.len() >= self.max_plies => Some(EndReason::Draw),
+                None => None,
+            };
+            if let Some(reason) = ended {
+                return self.end(reason)?.ok_or(DxpError::Aborted);
+            }
+            if self.our_turn() {
+                let started = Instant::now();
+                let (j, i, m) = player.choose_move(&self.game)?;
+                self.send_move(j, i, &m, started.elapsed())?;
+            } else if let DxpEvent::Ended(result) = self.receive()? {
+                return result.ok_or(DxpError::Aborted);
+            }
+        }
+    }
+}
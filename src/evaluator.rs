@@ -0,0 +1,258 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::checkers::Checkers;
+
+pub const WEIGHTS_FILE: &str = "weights.txt";
+
+// Evaluators score a position from black's point of view: positive is good
+// for black, negative is good for red. The search flips the sign for red.
+pub trait Evaluator: Sync {
+    fn evaluate(&self, board: &Checkers) -> i32;
+}
+
+// Lets one evaluator be shared between the GUI and its search threads.
+impl<E: Evaluator + Send + ?Sized> Evaluator for Arc<E> {
+    fn evaluate(&self, board: &Checkers) -> i32 {
+        (**self).evaluate(board)
+    }
+}
+
+// Which evaluator the engine plays with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvaluatorKind {
+    #[default]
+    Weighted,
+    Neural,
+}
+
+impl EvaluatorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvaluatorKind::Weighted => "weighted",
+            EvaluatorKind::Neural => "neural",
+        }
+    }
+}
+
+impl fmt::Display for EvaluatorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for EvaluatorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "weighted" => Ok(EvaluatorKind::Weighted),
+            "neural" => Ok(EvaluatorKind::Neural),
+            other => Err(format!("unknown evaluator {}", other)),
+        }
+    }
+}
+
+// The original hand-picked evaluation: material counts 5 per point and each
+// available jump counts 1 for the side that can make it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaterialEvaluator;
+
+impl Evaluator for MaterialEvaluator {
+    fn evaluate(&self, board: &Checkers) -> i32 {
+        let mut copy = *board;
+        let mut score = 0;
+        score += copy.count_material() * 5;
+        score += copy.count_vulnerable_red();
+        score -= copy.count_vulnerable_black();
+        score
+    }
+}
+
+pub const FEATURE_COUNT: usize = 10;
+pub const FEATURE_NAMES: [&str; FEATURE_COUNT] = [
+    "material",
+    "vulnerable_red",
+    "vulnerable_black",
+    "back_rank",
+    "center",
+    "mobility",
+    "tempo",
+    "runaway",
+    "king_center",
+    "trapped_kings",
+];
+
+// Raw evaluation terms, each counted from black's point of view before any
+// weight is applied.
+pub fn features(board: &Checkers) -> [i32; FEATURE_COUNT] {
+    let mut copy = *board;
+    let mut black_turn = *board;
+    black_turn.current_turn = 1;
+    let mut red_turn = *board;
+    red_turn.current_turn = -1;
+    let black_moves = black_turn.get_all_moves();
+    let red_moves = red_turn.get_all_moves();
+
+    let mut back_rank = 0;
+    let mut center = 0;
+    let mut tempo = 0;
+    let mut runaway = 0;
+    let mut king_center = 0;
+    let mut trapped_kings = 0;
+    for (j, row) in board.board_state.iter().enumerate() {
+        for (i, &piece) in row.iter().enumerate() {
+            if piece == 0 {
+                continue;
+            }
+            let side = piece.signum();
+            if piece == 1 && j == 7 || piece == -1 && j == 0 {
+                back_rank += side;
+            }
+            if (3..=4).contains(&j) && (2..=5).contains(&i) {
+                center += side;
+            }
+            if piece.abs() == 1 {
+                tempo += side * if piece > 0 { 7 - j as i32 } else { j as i32 };
+                if is_runaway(board, j, i) {
+                    runaway += side;
+                }
+            } else {
+                let distance = ((2 * j as i32 - 7).abs() + (2 * i as i32 - 7).abs()) / 2;
+                king_center += side * (7 - distance);
+                let moves = if piece > 0 { &black_moves } else { &red_moves };
+                if !moves.iter().any(|(from_j, from_i, _)| (*from_j, *from_i) == (j, i)) {
+                    trapped_kings += side;
+                }
+            }
+        }
+    }
+    [
+        copy.count_material(),
+        copy.count_vulnerable_red(),
+        copy.count_vulnerable_black(),
+        back_rank,
+        center,
+        black_moves.len() as i32 - red_moves.len() as i32,
+        tempo,
+        runaway,
+        king_center,
+        trapped_kings,
+    ]
+}
+
+// A man with an empty square ahead and no enemy piece anywhere in the cone
+// between it and its promotion row.
+fn is_runaway(board: &Checkers, j: usize, i: usize) -> bool {
+    let piece = board.board_state[j][i];
+    let rows: Vec<usize> = if piece > 0 { (0..j).rev().collect() } else { (j + 1..8).collect() };
+    let mut free_step = false;
+    for (distance, row) in rows.into_iter().enumerate() {
+        let reach = distance as i32 + 1;
+        for column in (i as i32 - reach).max(0)..=(i as i32 + reach).min(7) {
+            let other = board.board_state[row][column as usize];
+            if other * piece < 0 {
+                return false;
+            }
+            if distance == 0 && (column - i as i32).abs() == 1 && other == 0 {
+                free_step = true;
+            }
+        }
+    }
+    free_step
+}
+
+// One weight per feature. The first three defaults reproduce
+// `MaterialEvaluator`; the positional terms are small nudges on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weights(pub [i32; FEATURE_COUNT]);
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights([5, 1, -1, 1, 1, 1, 1, 3, 1, -2])
+    }
+}
+
+impl Weights {
+    // Stored as `name=value` lines; missing names keep their default.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut weights = Weights::default();
+        for line in fs::read_to_string(path)?.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                let index = FEATURE_NAMES.iter().position(|name| *name == key.trim());
+                let value = value.trim().parse::<i32>().ok();
+                match (index, value) {
+                    (Some(index), Some(value)) => weights.0[index] = value,
+                    _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("bad weight line: {}", line))),
+                }
+            }
+        }
+        Ok(weights)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents: String = FEATURE_NAMES.iter().zip(self.0.iter())
+            .map(|(name, value)| format!("{}={}\n", name, value))
+            .collect();
+        fs::write(path, contents)
+    }
+
+    pub fn apply(&self, features: &[i32; FEATURE_COUNT]) -> i32 {
+        self.0.iter().zip(features.iter()).map(|(w, f)| w * f).sum()
+    }
+}
+
+// Linear evaluation over `features` with configurable, e.g. tuned, weights.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeightedEvaluator {
+    pub weights: Weights,
+}
+
+impl WeightedEvaluator {
+    pub fn new(weights: Weights) -> Self {
+        Self { weights }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(Weights::load(path)?))
+    }
+}
+
+// One line of an evaluation breakdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalTerm {
+    pub name: &'static str,
+    pub value: i32,
+    pub weight: i32,
+}
+
+impl EvalTerm {
+    pub fn score(&self) -> i32 {
+        self.value * self.weight
+    }
+}
+
+impl fmt::Display for EvalTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<17} {:>4} x {:>3} = {:>5}", self.name, self.value, self.weight, self.score())
+    }
+}
+
+impl WeightedEvaluator {
+    // Every term's raw value, weight and contribution, in `FEATURE_NAMES` order.
+    pub fn report(&self, board: &Checkers) -> Vec<EvalTerm> {
+        FEATURE_NAMES.iter().zip(features(board)).zip(self.weights.0)
+            .map(|((name, value), weight)| EvalTerm { name, value, weight })
+            .collect()
+    }
+}
+
+impl Evaluator for WeightedEvaluator {
+    fn evaluate(&self, board: &Checkers) -> i32 {
+        self.weights.apply(&features(board))
+    }
+}
@@ -0,0 +1,111 @@
+use crate::checkers::{Checkers, CheckersError, Move};
+use crate::player::Player;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    BlackWon,
+    RedWon,
+    Draw,
+}
+
+// A game in progress: the current position plus every move played to reach
+// it, stored as (from_j, from_i, move) like `get_best_move` returns them.
+#[derive(Clone)]
+pub struct Game {
+    pub start: Checkers,
+    pub checkers: Checkers,
+    pub history: Vec<(usize, usize, Move)>,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::from_position(Checkers::new().expect("could not initialize checkers"))
+    }
+
+    pub fn from_position(checkers: Checkers) -> Self {
+        Self {
+            start: checkers,
+            checkers,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<(usize, usize, Move)> {
+        self.checkers.get_all_moves()
+    }
+
+    pub fn is_legal(&self, j: usize, i: usize, m: &Move) -> bool {
+        self.legal_moves().iter().any(|(mj, mi, mm)| *mj == j && *mi == i && mm == m)
+    }
+
+    pub fn play(&mut self, j: usize, i: usize, m: &Move) -> Result<(), CheckersError> {
+        if !self.is_legal(j, i, m) {
+            return Err(CheckersError::ImpossibleMove);
+        }
+        self.checkers.make_move_from_enum(j, i, m)?;
+        self.history.push((j, i, m.clone()));
+        Ok(())
+    }
+
+    // Records whichever legal move turns the current position into
+    // `target`, for front ends that move pieces one jump at a time.
+    pub fn play_to(&mut self, target: &Checkers) -> Result<(), CheckersError> {
+        let (j, i, m) = self.legal_moves().into_iter().find(|(j, i, m)| {
+            let mut next = self.checkers;
+            next.make_move_from_enum(*j, *i, m).is_ok()
+                && next.board_state == target.board_state
+                && next.current_turn == target.current_turn
+        }).ok_or(CheckersError::ImpossibleMove)?;
+        self.play(j, i, &m)
+    }
+
+    // Takes back the last move by replaying the rest from the start.
+    pub fn undo(&mut self) -> Option<(usize, usize, Move)> {
+        let last = self.history.pop()?;
+        let mut checkers = self.start;
+        for (j, i, m) in &self.history {
+            checkers.make_move_from_enum(*j, *i, m).expect("recorded moves are legal");
+        }
+        self.checkers = checkers;
+        Some(last)
+    }
+
+    // The side to move loses when it has nothing left to play.
+    pub fn result(&self) -> Option<GameResult> {
+        if !self.legal_moves().is_empty() {
+            None
+        } else if self.checkers.current_turn > 0 {
+            Some(GameResult::RedWon)
+        } else {
+            Some(GameResult::BlackWon)
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Plays a full game between two players. Games that reach `max_moves` plies
+// without a winner are scored as draws.
+pub fn play_game(black: &mut dyn Player, red: &mut dyn Player, max_moves: usize) -> Result<(GameResult, Game), CheckersError> {
+    play_game_from(Game::new(), black, red, max_moves)
+}
+
+pub fn play_game_from(mut game: Game, black: &mut dyn Player, red: &mut dyn Player, max_moves: usize) -> Result<(GameResult, Game), CheckersError> {
+    while game.history.len() < max_moves {
+        if let Some(result) = game.result() {
+            return Ok((result, game));
+        }
+        let (j, i, m) = if game.checkers.current_turn > 0 {
+            black.choose_move(&game)?
+        } else {
+            red.choose_move(&game)?
+        };
+        game.play(j, i, &m)?;
+    }
+    let result = game.result().unwrap_or(GameResult::Draw);
+    Ok((result, game))
+}
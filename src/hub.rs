@@ -0,0 +1,339 @@
+use std::io::{self, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::book::OpeningBook;
+use crate::checkers::Checkers;
+use crate::evaluator::{Evaluator, EvaluatorKind, WeightedEvaluator, WEIGHTS_FILE};
+use crate::nn::{NeuralEvaluator, NN_FILE};
+use crate::pdn::{from_fen, move_to_notation, parse_move, square_coords, START_FEN};
+use crate::search::{default_threads, SearchOptions};
+use crate::tablebase::Tablebase;
+use crate::worker::SearchWorker;
+
+pub const ENGINE_NAME: &str = "HW4";
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Deep enough to never be reached in practice; time or `stop` ends the
+// search first.
+const MAX_DEPTH: usize = 64;
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(1);
+// How often a running search is checked for new iterations and its result.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// One protocol line: the command word followed by `name=value` pairs.
+// Values with spaces are quoted; bare words such as `start` or `infinite`
+// are kept with an empty value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HubCommand {
+    pub name: String,
+    pub args: Vec<(String, String)>,
+}
+
+impl HubCommand {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line.trim();
+        let mut words = Vec::new();
+        while !rest.is_empty() {
+            let end = rest.find([' ', '=']).unwrap_or(rest.len());
+            let key = rest[..end].to_string();
+            rest = &rest[end..];
+            let value = match rest.strip_prefix('=') {
+                Some(after) => {
+                    let (value, remaining) = match after.strip_prefix('"') {
+                        Some(quoted) => {
+                            let close = quoted.find('"')?;
+                            (&quoted[..close], &quoted[close + 1..])
+                        },
+                        None => after.split_at(after.find(' ').unwrap_or(after.len())),
+                    };
+                    rest = remaining;
+                    value.to_string()
+                },
+                None => String::new(),
+            };
+            words.push((key, value));
+            rest = rest.trim_start();
+        }
+        let mut words = words.into_iter();
+        let (name, _) = words.next()?;
+        Some(Self { name, args: words.collect() })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.args.iter().find(|(name, _)| name == key).map(|(_, value)| value.as_str())
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.args.iter().any(|(name, _)| name == key)
+    }
+}
+
+// A value as it goes on a protocol line, quoted if it has spaces.
+pub fn quote(value: &str) -> String {
+    if value.contains(' ') || value.is_empty() {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+// The Hub position string for 8x8: `B` or `W` for the side to move, then
+// squares 1 to 32 as `b`/`B` for a black man/king, `w`/`W` for a red one
+// (white in draughts terms) and `e` for empty.
+pub fn to_hub_position(board: &Checkers) -> String {
+    let mut text = String::from(if board.current_turn > 0 { "B" } else { "W" });
+    for number in 1..=32 {
+        let (j, i) = square_coords(number).expect("valid square");
+        text.push(match board.board_state[j][i] {
+            1 => 'b',
+            3 => 'B',
+            -1 => 'w',
+            -3 => 'W',
+            _ => 'e',
+        });
+    }
+    text
+}
+
+pub fn from_hub_position(text: &str) -> Option<Checkers> {
+    let mut chars = text.trim().chars();
+    let current_turn = match chars.next()? {
+        'B' => 1,
+        'W' => -1,
+        _ => return None,
+    };
+    let mut board_state = [[0; 8]; 8];
+    let mut count = 0;
+    for (index, c) in chars.enumerate() {
+        let (j, i) = square_coords(index + 1)?;
+        board_state[j][i] = match c {
+            'b' => 1,
+            'B' => 3,
+            'w' => -1,
+            'W' => -3,
+            'e' => 0,
+            _ => return None,
+        };
+        count += 1;
+    }
+    if count == 32 {
+        Some(Checkers::from_position(board_state, current_turn))
+    } else {
+        None
+    }
+}
+
+fn start_position() -> Checkers {
+    from_fen(START_FEN).expect("valid start position")
+}
+
+// The engine side of the Hub protocol as spoken by Scan and the GUIs built
+// for it. Moves are written the way PDN writes them, with every landing
+// square of a capture.
+pub struct HubEngine {
+    board: Checkers,
+    options: SearchOptions,
+    evaluator: Arc<dyn Evaluator + Send>,
+    worker: Option<SearchWorker>,
+    // Iterations of the running search already sent as info lines.
+    reported: usize,
+    // The time a ponder search gets once the GUI says the guess came true.
+    ponder_budget: Option<Option<Duration>>,
+}
+
+impl HubEngine {
+    pub fn new(evaluator: Arc<dyn Evaluator + Send>) -> Self {
+        Self {
+            board: start_position(),
+            options: SearchOptions {
+                time_budget: Some(DEFAULT_MOVE_TIME),
+                threads: default_threads(),
+                ..SearchOptions::fixed_depth(MAX_DEPTH)
+            },
+            evaluator,
+            worker: None,
+            reported: 0,
+            ponder_budget: None,
+        }
+    }
+
+    pub fn with_tablebase(mut self, tablebase: Option<&'static Tablebase>) -> Self {
+        self.options.tablebase = tablebase;
+        self
+    }
+
+    pub fn with_book(mut self, book: Option<&'static OpeningBook>) -> Self {
+        self.options.book = book;
+        self
+    }
+
+    // Handles one line from the GUI. Returns false once it asks the engine
+    // to quit.
+    pub fn handle(&mut self, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+        let Some(command) = HubCommand::parse(line) else {
+            return Ok(true);
+        };
+        match command.name.as_str() {
+            "hub" => {
+                writeln!(out, "id name={} version={}", ENGINE_NAME, ENGINE_VERSION)?;
+                writeln!(out, "param name=threads value={} type=int min=1 max=64", self.options.threads)?;
+                writeln!(out, "param name=evaluator value=weighted type=enum values=\"weighted neural\"")?;
+                writeln!(out, "wait")?;
+            },
+            "init" => writeln!(out, "ready")?,
+            "ping" => writeln!(out, "pong")?,
+            "new-game" => {
+                self.worker = None;
+                self.board = start_position();
+            },
+            "pos" => self.set_position(&command, out)?,
+            "level" => self.set_level(&command),
+            "set-param" => self.set_param(&command, out)?,
+            "go" => self.go(&command, out)?,
+            "ponder-hit" => {
+                if let (Some(worker), Some(budget)) = (&mut self.worker, self.ponder_budget.take()) {
+                    worker.start_clock(budget);
+                }
+            },
+            "stop" => {
+                if let Some(worker) = &self.worker {
+                    worker.cancel();
+                }
+            },
+            "quit" => return Ok(false),
+            other => writeln!(out, "error message={}", quote(&format!("unknown command {}", other)))?,
+        }
+        Ok(true)
+    }
+
+    fn set_position(&mut self, command: &HubCommand, out: &mut dyn Write) -> io::Result<()> {
+        let board = match (command.get("pos"), command.get("fen")) {
+            (Some(text), _) => from_hub_position(text),
+            (None, Some(fen)) => from_fen(fen),
+            (None, None) => Some(start_position()),
+        };
+        let Some(mut board) = board else {
+            return writeln!(out, "error message=\"bad position\"");
+        };
+        for text in command.get("moves").unwrap_or("").split_whitespace() {
+            let played = parse_move(&board, text).map(|(j, i, m)| board.make_move_from_enum(j, i, &m));
+            if !matches!(played, Some(Ok(_))) {
+                return writeln!(out, "error message={}", quote(&format!("illegal move {}", text)));
+            }
+        }
+        self.worker = None;
+        self.board = board;
+        Ok(())
+    }
+
+    // `level depth=N`, `level move-time=S`, `level time=S inc=S [moves=N]`
+    // or `level infinite`. Each level replaces the one before.
+    fn set_level(&mut self, command: &HubCommand) {
+        let seconds = |key: &str| command.get(key).and_then(|v| v.parse::<f64>().ok()).filter(|s| *s >= 0.);
+        self.options.max_depth = command.get("depth").and_then(|v| v.parse().ok()).unwrap_or(MAX_DEPTH);
+        self.options.time_budget = if command.has("infinite") || command.has("depth") {
+            None
+        } else if let Some(move_time) = seconds("move-time") {
+            Some(Duration::from_secs_f64(move_time))
+        } else if let Some(time) = seconds("time") {
+            // spread the clock over the moves left, keeping most of the increment
+            let moves = command.get("moves").and_then(|v| v.parse::<f64>().ok()).unwrap_or(30.).max(1.);
+            Some(Duration::from_secs_f64(time / moves + 0.8 * seconds("inc").unwrap_or(0.)))
+        } else {
+            Some(DEFAULT_MOVE_TIME)
+        };
+    }
+
+    fn set_param(&mut self, command: &HubCommand, out: &mut dyn Write) -> io::Result<()> {
+        let value = command.get("value").unwrap_or("");
+        match command.get("name") {
+            Some("threads") => match value.parse::<usize>() {
+                Ok(threads) if threads > 0 => self.options.threads = threads,
+                _ => writeln!(out, "error message={}", quote(&format!("bad threads {}", value)))?,
+            },
+            Some("evaluator") => match value.parse::<EvaluatorKind>() {
+                Ok(EvaluatorKind::Weighted) => self.evaluator = Arc::new(WeightedEvaluator::load(WEIGHTS_FILE).unwrap_or_default()),
+                Ok(EvaluatorKind::Neural) => match NeuralEvaluator::load(NN_FILE) {
+                    Ok(neural) => self.evaluator = Arc::new(neural),
+                    Err(e) => writeln!(out, "error message={}", quote(&format!("could not load {}: {}", NN_FILE, e)))?,
+                },
+                Err(e) => writeln!(out, "error message={}", quote(&e))?,
+            },
+            name => writeln!(out, "error message={}", quote(&format!("unknown param {}", name.unwrap_or(""))))?,
+        }
+        Ok(())
+    }
+
+    // `go think` searches within the level, `go ponder` without a limit
+    // until `ponder-hit` starts the clock, and `go analyze` until `stop`.
+    fn go(&mut self, command: &HubCommand, out: &mut dyn Write) -> io::Result<()> {
+        if self.board.get_all_moves().is_empty() {
+            return writeln!(out, "done");
+        }
+        let mut options = self.options;
+        self.ponder_budget = None;
+        if command.has("ponder") {
+            self.ponder_budget = Some(options.time_budget);
+            options.time_budget = None;
+        } else if command.has("analyze") {
+            options.time_budget = None;
+            options.max_depth = MAX_DEPTH;
+        }
+        self.reported = 0;
+        self.worker = Some(SearchWorker::spawn(self.board, options, Arc::clone(&self.evaluator)));
+        Ok(())
+    }
+
+    // Sends info lines for newly finished iterations and `done` with the
+    // move once the search is over.
+    pub fn poll(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let Some(worker) = &self.worker else {
+            return Ok(());
+        };
+        if let Some(stats) = worker.stats() {
+            // iterations count their own nodes and time; the protocol wants totals
+            let mut nodes = 0;
+            let mut elapsed = Duration::ZERO;
+            for (index, iteration) in stats.iterations.iter().enumerate() {
+                nodes += iteration.nodes;
+                elapsed += iteration.elapsed;
+                if index < self.reported {
+                    continue;
+                }
+                let (j, i, m) = &iteration.best_move;
+                let nps = (nodes as f64 / elapsed.as_secs_f64().max(1e-6)) as u64;
+                writeln!(out, "info depth={} score={} nodes={} time={:.3} nps={} pv={}",
+                    iteration.depth, iteration.score, nodes, elapsed.as_secs_f64(), nps, quote(&move_to_notation(*j, *i, m)))?;
+            }
+            self.reported = stats.iterations.len();
+        }
+        if let Some(result) = worker.poll() {
+            self.worker = None;
+            match result {
+                Ok((j, i, m)) => writeln!(out, "done move={}", move_to_notation(j, i, &m))?,
+                Err(e) => writeln!(out, "error message={}", quote(&e.to_string()))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Runs the protocol over lines read on another thread, so a search can be
+// stopped while it runs. Ends on `quit` or when the input closes.
+pub fn run(lines: Receiver<String>, out: &mut dyn Write, mut engine: HubEngine) -> io::Result<()> {
+    loop {
+        match lines.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => {
+                if !engine.handle(&line, out)? {
+                    return Ok(());
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        engine.poll(out)?;
+        out.flush()?;
+    }
+}
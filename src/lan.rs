@@ -0,0 +1,340 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::checkers::{Checkers, CheckersError, Move};
+use crate::game::Game;
+use crate::pdn::{from_fen, move_to_notation, parse_move, to_fen, START_FEN};
+
+pub const DEFAULT_PORT: u16 = 27532;
+pub const PROTOCOL_VERSION: u32 = 1;
+// How often the guest tries to reconnect and the host checks for a guest.
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+// One line of the protocol. Moves are in notation, numbered by ply so a
+// move meant for another position is caught.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LanMessage {
+    // Sent by the guest on every connection; the host answers with the game.
+    Hello { version: u32 },
+    // The whole game as the host has it: the guest's color, the start
+    // position and every move since.
+    Game { guest_color: i32, start: String, moves: Vec<String> },
+    Move { ply: usize, notation: String },
+    // A move that did not fit the receiver's game.
+    Reject { ply: usize, reason: String },
+}
+
+impl LanMessage {
+    pub fn to_line(&self) -> String {
+        match self {
+            LanMessage::Hello { version } => format!("hello {}", version),
+            LanMessage::Game { guest_color, start, moves } => {
+                let color = if *guest_color > 0 { "black" } else { "red" };
+                let mut line = format!("game {} {}", color, start);
+                for notation in moves {
+                    line.push(' ');
+                    line.push_str(notation);
+                }
+                line
+            },
+            LanMessage::Move { ply, notation } => format!("move {} {}", ply, notation),
+            LanMessage::Reject { ply, reason } => format!("reject {} {}", ply, reason),
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        match words.next()? {
+            "hello" => Some(LanMessage::Hello { version: words.next()?.parse().ok()? }),
+            "game" => {
+                let guest_color = match words.next()? {
+                    "black" => 1,
+                    "red" => -1,
+                    _ => return None,
+                };
+                let start = words.next()?.to_string();
+                Some(LanMessage::Game { guest_color, start, moves: words.map(str::to_string).collect() })
+            },
+            "move" => Some(LanMessage::Move { ply: words.next()?.parse().ok()?, notation: words.next()?.to_string() }),
+            "reject" => Some(LanMessage::Reject { ply: words.next()?.parse().ok()?, reason: words.collect::<Vec<_>>().join(" ") }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanRole {
+    Host,
+    Guest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanStatus {
+    // Nobody has connected yet.
+    Waiting,
+    Connected,
+    // The connection dropped. The game is kept until the other side is
+    // back: the host takes the next guest to connect, the guest keeps
+    // retrying.
+    Disconnected,
+}
+
+// What changed in `poll`, for the front end to redraw.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LanUpdate {
+    Connected,
+    Disconnected,
+    // The opponent made this move.
+    Moved(usize, usize, Move),
+    // The game was replaced by the host's copy.
+    Synced,
+    Error(String),
+}
+
+// Sent from the network threads. Each connection has its own number so a
+// late close of an old one is not taken for the current one.
+enum LinkEvent {
+    Connected(u64, TcpStream),
+    Line(u64, String),
+    Closed(u64),
+}
+
+// A game between two windows over the network. The host listens on a port
+// and holds the game everyone agrees on; the guest joins it. Both check
+// every move against their own board, and the host sends the whole game to
+// the guest on every connection, so after a disconnect play resumes where
+// it stopped. A guest move lost with the connection has to be made again.
+pub struct LanSession {
+    pub role: LanRole,
+    // The color this side plays; the guest learns it from the host.
+    pub color: i32,
+    pub game: Game,
+    pub status: LanStatus,
+    // Where the host listens or the guest connects.
+    pub address: String,
+    connection: Option<(u64, TcpStream)>,
+    // The guest has the host's game for the current connection.
+    synced: bool,
+    events: Receiver<LinkEvent>,
+    stop: Arc<AtomicBool>,
+}
+
+impl LanSession {
+    // Listens on `port` (0 picks a free one) for a guest to play `color`
+    // against from `start`.
+    pub fn host(port: u16, color: i32, start: Checkers) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?.to_string();
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            let mut id = 0;
+            while !thread_stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        id += 1;
+                        let connection_sender = sender.clone();
+                        thread::spawn(move || serve(id, stream, &connection_sender));
+                    },
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(RETRY_INTERVAL / 5),
+                    Err(_) => thread::sleep(RETRY_INTERVAL),
+                }
+            }
+        });
+        Ok(Self::new(LanRole::Host, color, Game::from_position(start), address, events, stop))
+    }
+
+    // Joins the host at `address`, adding the default port when it has none.
+    // Connecting happens in the background and is retried until it works.
+    pub fn join(address: &str) -> Self {
+        let address = if address.contains(':') { address.to_string() } else { format!("{}:{}", address, DEFAULT_PORT) };
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let target = address.clone();
+        thread::spawn(move || {
+            let mut id = 0;
+            while !thread_stop.load(Ordering::Relaxed) {
+                if let Ok(stream) = TcpStream::connect(target.as_str()) {
+                    id += 1;
+                    serve(id, stream, &sender);
+                }
+                thread::sleep(RETRY_INTERVAL);
+            }
+        });
+        let start = from_fen(START_FEN).expect("valid start position");
+        Self::new(LanRole::Guest, 0, Game::from_position(start), address, events, stop)
+    }
+
+    fn new(role: LanRole, color: i32, game: Game, address: String, events: Receiver<LinkEvent>, stop: Arc<AtomicBool>) -> Self {
+        Self { role, color, game, status: LanStatus::Waiting, address, connection: None, synced: role == LanRole::Host, events, stop }
+    }
+
+    // Whether the local player may move now.
+    pub fn can_move(&self) -> bool {
+        self.status == LanStatus::Connected && self.synced && self.game.checkers.current_turn.signum() == self.color && self.game.result().is_none()
+    }
+
+    // Plays the local player's move, which turned the game into `board`,
+    // and sends it.
+    pub fn play_to(&mut self, board: &Checkers) -> Result<(), CheckersError> {
+        if !self.can_move() {
+            return Err(CheckersError::ImpossibleMove);
+        }
+        self.game.play_to(board)?;
+        let (j, i, m) = self.game.history.last().expect("a move was just played");
+        let notation = move_to_notation(*j, *i, m);
+        self.send(&LanMessage::Move { ply: self.game.history.len() - 1, notation });
+        Ok(())
+    }
+
+    // Starts over from `start`. Only the host decides; the guest gets the
+    // new game when the host sends it.
+    pub fn new_game(&mut self, start: Checkers) {
+        if self.role == LanRole::Host {
+            self.game = Game::from_position(start);
+            self.send_game();
+        }
+    }
+
+    // Handles everything that arrived since the last call.
+    pub fn poll(&mut self) -> Vec<LanUpdate> {
+        let mut updates = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                LinkEvent::Connected(id, stream) => {
+                    if let Some((_, old)) = self.connection.replace((id, stream)) {
+                        let _ = old.shutdown(Shutdown::Both);
+                    }
+                    self.status = LanStatus::Connected;
+                    if self.role == LanRole::Guest {
+                        self.synced = false;
+                        self.send(&LanMessage::Hello { version: PROTOCOL_VERSION });
+                    }
+                    updates.push(LanUpdate::Connected);
+                },
+                LinkEvent::Line(id, line) if self.is_current(id) => match LanMessage::parse(&line) {
+                    Some(message) => updates.extend(self.receive(message)),
+                    None => updates.push(LanUpdate::Error(format!("bad message {:?}", line))),
+                },
+                LinkEvent::Closed(id) if self.is_current(id) => {
+                    self.connection = None;
+                    self.status = LanStatus::Disconnected;
+                    updates.push(LanUpdate::Disconnected);
+                },
+                _ => (),
+            }
+        }
+        updates
+    }
+
+    fn is_current(&self, id: u64) -> bool {
+        self.connection.as_ref().is_some_and(|(current, _)| *current == id)
+    }
+
+    fn receive(&mut self, message: LanMessage) -> Option<LanUpdate> {
+        match (message, self.role) {
+            (LanMessage::Hello { version }, LanRole::Host) => {
+                if version != PROTOCOL_VERSION {
+                    return Some(LanUpdate::Error(format!("guest speaks protocol {}, not {}", version, PROTOCOL_VERSION)));
+                }
+                self.send_game();
+                None
+            },
+            (LanMessage::Game { guest_color, start, moves }, LanRole::Guest) => match replay(&start, &moves) {
+                Some(game) => {
+                    self.game = game;
+                    self.color = guest_color;
+                    self.synced = true;
+                    Some(LanUpdate::Synced)
+                },
+                None => Some(LanUpdate::Error("the host's game has an illegal move".to_string())),
+            },
+            (LanMessage::Move { ply, notation }, _) => {
+                let theirs = self.game.checkers.current_turn.signum() != self.color;
+                let found = parse_move(&self.game.checkers, &notation).filter(|_| theirs && self.synced && ply == self.game.history.len());
+                match found {
+                    Some((j, i, m)) => {
+                        self.game.play(j, i, &m).expect("parsed moves are legal");
+                        Some(LanUpdate::Moved(j, i, m))
+                    },
+                    None => {
+                        let reason = format!("{} does not fit ply {}", notation, self.game.history.len());
+                        self.send(&LanMessage::Reject { ply, reason: reason.clone() });
+                        self.resync();
+                        Some(LanUpdate::Error(reason))
+                    },
+                }
+            },
+            (LanMessage::Reject { reason, .. }, _) => {
+                self.resync();
+                Some(LanUpdate::Error(format!("move rejected: {}", reason)))
+            },
+            (message, _) => Some(LanUpdate::Error(format!("unexpected message {:?}", message.to_line()))),
+        }
+    }
+
+    // After a disagreement the host's game wins: the host sends it again and
+    // the guest waits for it.
+    fn resync(&mut self) {
+        match self.role {
+            LanRole::Host => self.send_game(),
+            LanRole::Guest => self.synced = false,
+        }
+    }
+
+    fn send_game(&mut self) {
+        let moves = self.game.history.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect();
+        self.send(&LanMessage::Game { guest_color: -self.color, start: to_fen(&self.game.start), moves });
+    }
+
+    // A failed write means the connection is going; its reader reports the
+    // close, and the next connection brings the games back in line.
+    fn send(&mut self, message: &LanMessage) {
+        if let Some((_, stream)) = &mut self.connection {
+            let _ = stream.write_all(format!("{}\n", message.to_line()).as_bytes());
+        }
+    }
+}
+
+impl Drop for LanSession {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some((_, stream)) = &self.connection {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+// Reports the connection and every line read from it until it closes.
+fn serve(id: u64, stream: TcpStream, sender: &Sender<LinkEvent>) {
+    let Ok(writer) = stream.try_clone() else { return };
+    if stream.set_nonblocking(false).is_err() || sender.send(LinkEvent::Connected(id, writer)).is_err() {
+        return;
+    }
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        if sender.send(LinkEvent::Line(id, line)).is_err() {
+            return;
+        }
+    }
+    let _ = sender.send(LinkEvent::Closed(id));
+}
+
+// The game from a start position and moves in notation, if every move is
+// legal.
+fn replay(start: &str, moves: &[String]) -> Option<Game> {
+    let mut game = Game::from_position(from_fen(start)?);
+    for notation in moves {
+        let (j, i, m) = parse_move(&game.checkers, notation)?;
+        game.play(j, i, &m).ok()?;
+    }
+    Some(game)
+}
@@ -0,0 +1,24 @@
+pub mod analysis;
+pub mod book;
+pub mod checkers;
+pub mod difficulty;
+pub mod dxp;
+pub mod evaluator;
+pub mod game;
+pub mod hub;
+pub mod lan;
+pub mod match_runner;
+pub mod mcts;
+pub mod mode;
+pub mod nn;
+pub mod pdn;
+pub mod player;
+pub mod puzzle;
+pub mod search;
+pub mod settings;
+pub mod solver;
+pub mod tablebase;
+pub mod terminal;
+pub mod tt;
+pub mod tuning;
+pub mod worker;
@@ -1,8 +1,35 @@
-mod checkers;
-
-use checkers::Checkers;
+use hw4::analysis::{analyze_game, annotated_pdn, summary, Thresholds, ANALYSIS_FILE};
+use hw4::book::{OpeningBook, BOOK_FILE};
+use hw4::checkers::{Checkers, Move};
+use hw4::difficulty::Difficulty;
+use hw4::evaluator::{Evaluator, EvaluatorKind, WeightedEvaluator, WEIGHTS_FILE};
+use hw4::game::Game;
+use hw4::lan::{LanRole, LanSession, LanStatus, LanUpdate, DEFAULT_PORT};
+use hw4::mode::GameMode;
+use hw4::nn::{NeuralEvaluator, NN_FILE};
+use hw4::pdn::{from_fen, PdnGame, START_FEN};
+use hw4::puzzle::{load_puzzles, PuzzleSession, PUZZLES_FILE};
+use hw4::search::SearchOptions;
+use hw4::settings::{Settings, SETTINGS_FILE};
+use hw4::tablebase::{Tablebase, TABLEBASE_FILE};
+use hw4::worker::SearchWorker;
 use image::GenericImageView;
 use macroquad::prelude::*;
+use macroquad::ui::root_ui;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Pause before moves the player did not make, so they can be followed: the
+// AI's moves against itself and the scripted replies in a puzzle.
+const MOVE_DELAY: f64 = 0.5;
+
+// Sent from the analysis thread to the frame loop.
+enum AnalysisUpdate {
+    Progress(usize, usize),
+    Finished(String),
+}
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct Piece {
@@ -27,6 +54,29 @@ impl Piece {
 async fn main() {
     let mut checkers: Checkers = Checkers::new().expect("could not initialize checkers");
     let mut current_dragged: Option<(usize, usize)> = None;
+    let mut settings = Settings::load(SETTINGS_FILE);
+    let mut lan = start_lan(&std::env::args().collect::<Vec<String>>());
+    let tablebase = Tablebase::load_static(TABLEBASE_FILE).ok();
+    if let Some(tb) = tablebase {
+        println!("loaded endgame tablebase up to {} pieces", tb.max_pieces);
+    }
+    let book = OpeningBook::load_static(BOOK_FILE).ok();
+    if let Some(book) = book {
+        println!("loaded opening book with {} positions", book.len());
+    }
+    // the settings' search with whatever the engine has loaded
+    let search_options = move |settings: &Settings| SearchOptions { tablebase, book, ..settings.search_options() };
+    let weighted = WeightedEvaluator::load(WEIGHTS_FILE).unwrap_or_default();
+    let evaluator: Arc<dyn Evaluator + Send> = match settings.evaluator {
+        EvaluatorKind::Neural => match NeuralEvaluator::load(NN_FILE) {
+            Ok(neural) => Arc::new(neural),
+            Err(e) => {
+                eprintln!("could not load {}: {}, using the weighted evaluator", NN_FILE, e);
+                Arc::new(weighted)
+            },
+        },
+        EvaluatorKind::Weighted => Arc::new(weighted),
+    };
     let mut piece_board: [[Option<Piece>; 8]; 8] = [[None; 8]; 8];
     let black_piece_image = load_png("media/bp.png").expect("couldn't load");
     let black_king_image = load_png("media/bk.png").expect("couldn't load");
@@ -41,8 +91,139 @@ async fn main() {
     reset_piece_board(&mut piece_board);
 
 
+    let mut ai_worker: Option<SearchWorker> = None;
+    let mut ponder_worker: Option<SearchWorker> = None;
+    let mut hint_worker: Option<SearchWorker> = None;
+    let mut hint: Option<(usize, usize, Move)> = None;
+    let mut game = Game::from_position(checkers);
+    let mut finished_game: Option<Game> = None;
+    let mut analysis: Option<Receiver<AnalysisUpdate>> = None;
+    let mut analysis_status = String::new();
+    let puzzles = load_puzzles(PUZZLES_FILE).unwrap_or_default();
+    let mut puzzle: Option<PuzzleSession> = None;
+    let mut puzzle_number = 0;
+    // Set to abandon the current game at the start of the next frame. Puzzle
+    // mode starts with its first puzzle.
+    let mut new_game = settings.mode == GameMode::Puzzle && lan.is_none();
+    let mut last_move_at = get_time();
+
     loop {
 
+        if let Some(result) = ai_worker.as_ref().and_then(|worker| worker.poll()) {
+            ai_worker = None;
+            checkers.calculating = false;
+            let played = result.and_then(|(j, i, m)| checkers.make_move_from_enum(j, i, &m));
+            match played {
+                Ok(_) => {
+                    record_move(&mut game, &checkers);
+                    sync_piece_board(&checkers, &mut piece_board);
+                    last_move_at = get_time();
+                    // only worth pondering while a human is thinking
+                    if settings.ponder && !checkers.is_game_over() && !settings.mode.is_ai(checkers.current_turn) {
+                        ponder_worker = Some(SearchWorker::ponder(checkers, search_options(&settings), Arc::clone(&evaluator)));
+                    }
+                },
+                Err(e) => {
+                    eprintln!("AI could not move: {}", e);
+                    new_game = true;
+                },
+            }
+        }
+
+        if let Some(session) = &mut lan {
+            for update in session.poll() {
+                match update {
+                    LanUpdate::Moved(..) | LanUpdate::Synced => {
+                        checkers = session.game.checkers;
+                        game = session.game.clone();
+                        sync_piece_board(&checkers, &mut piece_board);
+                        current_dragged = None;
+                        hint = None;
+                        hint_worker = None;
+                        last_move_at = get_time();
+                    },
+                    LanUpdate::Error(e) => eprintln!("network: {}", e),
+                    LanUpdate::Connected | LanUpdate::Disconnected => (),
+                }
+            }
+        }
+
+        let game_over = puzzle.is_none() && checkers.is_game_over();
+        if new_game || game_over {
+            new_game = false;
+            ai_worker = None;
+            ponder_worker = None;
+            hint_worker = None;
+            hint = None;
+            reset_piece_board(&mut piece_board);
+            current_dragged = None;
+            checkers = Checkers::new().expect("could not initialize new checkers");
+            puzzle = None;
+            // over the network the host starts the next game and the guest
+            // waits for it
+            if let Some(session) = &mut lan {
+                session.new_game(checkers);
+            } else if settings.mode == GameMode::Puzzle && !puzzles.is_empty() {
+                let session = PuzzleSession::new(puzzles[puzzle_number % puzzles.len()].clone());
+                checkers = session.board;
+                sync_piece_board(&checkers, &mut piece_board);
+                puzzle = Some(session);
+            }
+            let previous = std::mem::replace(&mut game, Game::from_position(checkers));
+            if game_over {
+                // keep the finished game around so it can be analysed
+                finished_game = Some(previous);
+                analysis_status.clear();
+            }
+            last_move_at = get_time();
+        }
+
+        let ai_to_move = lan.is_none() && settings.mode.is_ai(checkers.current_turn);
+        let paused = settings.mode == GameMode::AiVsAi && get_time() - last_move_at < MOVE_DELAY;
+        if ai_to_move && ai_worker.is_none() && checkers.required_square.is_none() && !paused {
+            // think on a worker so the window keeps drawing
+            checkers.calculating = true;
+            hint = None;
+            hint_worker = None;
+            let options = search_options(&settings);
+            ai_worker = match ponder_worker.take() {
+                Some(mut worker) if worker.is_searching(&checkers) => {
+                    worker.start_clock(options.time_limit());
+                    Some(worker)
+                },
+                _ => Some(SearchWorker::spawn(checkers, options, Arc::clone(&evaluator))),
+            };
+        }
+
+        if let Some(session) = &mut puzzle {
+            if get_time() - last_move_at >= MOVE_DELAY && session.reply().is_some() {
+                checkers = session.board;
+                sync_piece_board(&checkers, &mut piece_board);
+                last_move_at = get_time();
+            }
+        }
+        // the player cannot move while the engine, a puzzle reply or the
+        // other side of a network game is due
+        let player_waits = ai_to_move
+            || puzzle.as_ref().is_some_and(|session| session.expected_move().is_none())
+            || lan.as_ref().is_some_and(|session| !session.can_move());
+
+        let updates: Vec<AnalysisUpdate> = analysis.as_ref().map(|receiver| receiver.try_iter().collect()).unwrap_or_default();
+        for update in updates {
+            match update {
+                AnalysisUpdate::Progress(done, total) => analysis_status = format!("Analyzing {}/{}", done, total),
+                AnalysisUpdate::Finished(message) => {
+                    analysis_status = message;
+                    analysis = None;
+                },
+            }
+        }
+
+        if let Some(result) = hint_worker.as_ref().and_then(|worker| worker.poll()) {
+            hint_worker = None;
+            hint = result.ok();
+        }
+
         clear_background(LIGHTGRAY);
         let black_turn = checkers.current_turn > 0;
         let game_size = screen_width().min(screen_height());
@@ -100,7 +281,7 @@ async fn main() {
                     }
                 } else if let Some(mut piece) = &mut piece_board[board_y][board_x] {
                     //println!("routed");
-                    if piece.piecekind > 0 && black_turn || piece.piecekind < 0 && !black_turn {
+                    if (piece.piecekind > 0 && black_turn || piece.piecekind < 0 && !black_turn) && !checkers.calculating && !player_waits {
                         if circle_radius.powi(2) > ((mouse_position().0 - piece.ui_position.1).powi(2) + (mouse_position().1 - piece.ui_position.0).powi(2)) {
                             piece.is_dragging = true;
                             current_dragged = Some((board_y, board_x));
@@ -113,44 +294,44 @@ async fn main() {
                 }
             } else {
                 if let Some(dragged_piece) = current_dragged {
-                    if checkers.can_make_move(
+                    if let Some(session) = &mut puzzle {
+                        // every jump has to follow the solution, so check each before making it
+                        if session.try_step(dragged_piece, (board_y, board_x)).is_ok() {
+                            checkers = session.board;
+                            hint = None;
+                            last_move_at = get_time();
+                        }
+                        sync_piece_board(&checkers, &mut piece_board);
+                    } else if checkers.can_make_move(
                         dragged_piece.0,
                             dragged_piece.1, 
                             board_y, 
                             board_x
                         ) {
-                            match checkers.make_move_then_ai(dragged_piece.0,
+                            match checkers.make_move(dragged_piece.0,
                                 dragged_piece.1, 
                                 board_y, 
                                 board_x
                             ) {
                                 Ok(_) => {
-                                    piece_board[board_y][board_x] = piece_board[dragged_piece.0][dragged_piece.1];
-                                    piece_board[dragged_piece.0][dragged_piece.1] = None;
-                                    if std::cmp::max(board_x, dragged_piece.1) - std::cmp::min(board_x, dragged_piece.1) > 1 {
-                                        piece_board[(board_y + dragged_piece.0) / 2][(board_x + dragged_piece.1) / 2] = None
-                                    }
-                                    println!("got here, y should become {}, x {}", board_y, board_x);
-                                    for j in 0..8 {
-                                        for i in 0..8 {
-                                            if checkers.board_state[j][i] != 0{
-                                                piece_board[j][i] = Some(Piece::new(j, i, checkers.board_state[j][i]));
-                                            } else {
-                                                piece_board[j][i] = None;
+                                    sync_piece_board(&checkers, &mut piece_board);
+                                    hint = None;
+                                    hint_worker = None;
+                                    // a multi-jump is recorded once it is complete
+                                    if checkers.required_square.is_none() {
+                                        record_move(&mut game, &checkers);
+                                        last_move_at = get_time();
+                                        if let Some(session) = &mut lan {
+                                            if let Err(e) = session.play_to(&checkers) {
+                                                eprintln!("could not send move: {}", e);
+                                                checkers = session.game.checkers;
+                                                game = session.game.clone();
+                                                sync_piece_board(&checkers, &mut piece_board);
                                             }
                                         }
                                     }
-                                    if checkers.is_game_over() {
-                                        reset_piece_board(&mut piece_board);
-                                        current_dragged = None;
-                                        checkers = Checkers::new().expect("could not initialize new checkers");
-                                    }
-                                },
-                                Err(_e) => {
-                                    reset_piece_board(&mut piece_board);
-                                    current_dragged = None;
-                                    checkers = Checkers::new().expect("could not initialize new checkers");
                                 },
+                                Err(_e) => new_game = true,
                             };
                         }
                 }
@@ -196,9 +377,248 @@ async fn main() {
             }
         }
 
+        if let Some((j, i, m)) = &hint {
+            draw_hint(*j, *i, m, offset_x, offset_y, sq_size);
+        }
+
+        draw_side_panel(&mut settings);
+        if !settings.ponder {
+            ponder_worker = None;
+        }
+        if let Some(session) = &lan {
+            draw_lan_panel(session);
+        } else if draw_mode_panel(&mut settings) {
+            new_game = true;
+        }
+        if settings.mode == GameMode::Puzzle && lan.is_none() && draw_puzzle_panel(puzzle.as_ref(), puzzle_number, puzzles.len()) {
+            puzzle_number += 1;
+            new_game = true;
+        }
+        if let Some(worker) = &ai_worker {
+            draw_thinking(worker);
+        } else if !player_waits && draw_hint_button(hint_worker.is_some()) && hint_worker.is_none() {
+            match &puzzle {
+                // in a puzzle the hint is the solution's next move
+                Some(session) => hint = session.expected_move(),
+                None => {
+                    // hints are for learning, so they come without the level's deliberate mistakes
+                    let options = SearchOptions { eval_noise: 0, blunder_chance: 0., multi_pv: 1, ..search_options(&settings) };
+                    hint_worker = Some(SearchWorker::spawn(checkers, options, Arc::clone(&evaluator)));
+                },
+            }
+        }
+        if let Some(finished) = &finished_game {
+            if draw_analysis_panel(analysis.is_some(), &analysis_status) && analysis.is_none() {
+                let options = SearchOptions {
+                    max_depth: 10,
+                    time_budget: Some(Duration::from_millis(300)),
+                    ..search_options(&settings)
+                };
+                analysis = Some(start_analysis(finished.clone(), options, Arc::clone(&evaluator)));
+            }
+        }
+
         next_frame().await;
     }
 
+    // `--host [port] [--color black|red]` waits for someone to join;
+    // `--join <address>` joins them. Without either the game is local.
+    fn start_lan(args: &[String]) -> Option<LanSession> {
+        let flag = args.iter().position(|a| a == "--host" || a == "--join")?;
+        if args[flag] == "--join" {
+            let Some(address) = args.get(flag + 1) else {
+                eprintln!("--join needs an address");
+                return None;
+            };
+            return Some(LanSession::join(address));
+        }
+        let port = args.get(flag + 1).and_then(|a| a.parse().ok()).unwrap_or(DEFAULT_PORT);
+        let red = args.windows(2).any(|pair| pair[0] == "--color" && pair[1] == "red");
+        let start = from_fen(START_FEN).expect("valid start position");
+        match LanSession::host(port, if red { -1 } else { 1 }, start) {
+            Ok(session) => Some(session),
+            Err(e) => {
+                eprintln!("could not listen on port {}: {}", port, e);
+                None
+            },
+        }
+    }
+
+    fn record_move(game: &mut Game, checkers: &Checkers) {
+        if let Err(e) = game.play_to(checkers) {
+            eprintln!("could not record move: {}", e);
+        }
+    }
+
+    // Re-searches every position of the finished game on its own thread and
+    // saves the annotated record to ANALYSIS_FILE.
+    fn start_analysis(game: Game, options: SearchOptions, evaluator: Arc<dyn Evaluator + Send>) -> Receiver<AnalysisUpdate> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let progress = sender.clone();
+            let analysis = analyze_game(&game, &options, &*evaluator, &Thresholds::default(), |done, total| {
+                let _ = progress.send(AnalysisUpdate::Progress(done, total));
+            });
+            let message = match std::fs::write(ANALYSIS_FILE, annotated_pdn(&PdnGame::from_game(&game), &analysis)) {
+                Ok(()) => format!("Saved to {}\n{}", ANALYSIS_FILE, summary(&game, &analysis).replace("; ", "\n")),
+                Err(e) => format!("could not write {}: {}", ANALYSIS_FILE, e),
+            };
+            let _ = sender.send(AnalysisUpdate::Finished(message));
+        });
+        receiver
+    }
+
+    fn draw_analysis_panel(running: bool, status: &str) -> bool {
+        let y = 35. + Difficulty::ALL.len() as f32 * 25. + 160.;
+        let clicked = !running && root_ui().button(vec2(5., y), "Analyze last game");
+        for (index, line) in status.lines().enumerate() {
+            root_ui().label(vec2(5., y + 25. + index as f32 * 20.), line);
+        }
+        clicked
+    }
+
+    fn draw_hint_button(searching: bool) -> bool {
+        let label = if searching { "Hint..." } else { "Hint" };
+        root_ui().button(vec2(5., 35. + Difficulty::ALL.len() as f32 * 25. + 40.), label)
+    }
+
+    // Outlines the piece to move and traces every square it lands on, so
+    // the whole of a multi-jump is visible.
+    fn draw_hint(j: usize, i: usize, m: &Move, offset_x: f32, offset_y: f32, sq_size: f32) {
+        let center = |(j, i): (usize, usize)| (offset_x + (i as f32 + 0.5) * sq_size, offset_y + (j as f32 + 0.5) * sq_size);
+        draw_rectangle_lines(offset_x + i as f32 * sq_size, offset_y + j as f32 * sq_size, sq_size, sq_size, 6., YELLOW);
+        let path = m.path(j, i);
+        for step in path.windows(2) {
+            let (x1, y1) = center(step[0]);
+            let (x2, y2) = center(step[1]);
+            draw_line(x1, y1, x2, y2, 4., YELLOW);
+            draw_circle_lines(x2, y2, sq_size / 4., 4., YELLOW);
+        }
+    }
+
+    // Shown while the AI searches. "Move now" stops the search, which then
+    // plays the best move it has found so far.
+    fn draw_thinking(worker: &SearchWorker) {
+        let dots = ".".repeat(1 + (get_time() * 2.) as usize % 3);
+        root_ui().label(vec2(5., 35. + Difficulty::ALL.len() as f32 * 25. + 40.), &format!("Thinking{}", dots));
+        if root_ui().button(vec2(5., 35. + Difficulty::ALL.len() as f32 * 25. + 65.), "Move now") {
+            worker.cancel();
+        }
+        if let Some(stats) = worker.stats() {
+            let lines = [
+                format!("depth {} ({})", stats.depth(), stats.seldepth),
+                format!("{} nodes", stats.nodes),
+                format!("{} knps", stats.nodes_per_second() / 1000),
+            ];
+            for (index, line) in lines.iter().enumerate() {
+                root_ui().label(vec2(5., 35. + Difficulty::ALL.len() as f32 * 25. + 90. + index as f32 * 20.), line);
+            }
+        }
+    }
+
+    fn sync_piece_board(checkers: &Checkers, piece_board: &mut [[Option<Piece>; 8]; 8]) {
+        for (j, row) in piece_board.iter_mut().enumerate() {
+            for (i, square) in row.iter_mut().enumerate() {
+                let kind = checkers.board_state[j][i];
+                *square = if kind != 0 { Some(Piece::new(j, i, kind)) } else { None };
+            }
+        }
+    }
+
+    fn draw_side_panel(settings: &mut Settings) {
+        root_ui().label(vec2(5., 10.), "Difficulty");
+        for (index, difficulty) in Difficulty::ALL.iter().enumerate() {
+            let label = if *difficulty == settings.difficulty {
+                format!("> {}", difficulty)
+            } else {
+                difficulty.to_string()
+            };
+            if root_ui().button(vec2(5., 35. + index as f32 * 25.), label) {
+                settings.difficulty = *difficulty;
+                if let Err(e) = settings.save(SETTINGS_FILE) {
+                    eprintln!("could not save settings: {}", e);
+                }
+            }
+        }
+        let ponder_label = if settings.ponder { "Ponder: on" } else { "Ponder: off" };
+        if root_ui().button(vec2(5., 35. + Difficulty::ALL.len() as f32 * 25. + 5.), ponder_label) {
+            settings.ponder = !settings.ponder;
+            if let Err(e) = settings.save(SETTINGS_FILE) {
+                eprintln!("could not save settings: {}", e);
+            }
+        }
+    }
+
+    // Mode buttons on the right edge of the window. Returns true when the mode
+    // changed, which starts a new game.
+    fn draw_mode_panel(settings: &mut Settings) -> bool {
+        let x = screen_width() - 110.;
+        root_ui().label(vec2(x, 10.), "Mode");
+        let mut changed = false;
+        for (index, mode) in GameMode::ALL.iter().enumerate() {
+            let label = if *mode == settings.mode {
+                format!("> {}", mode.label())
+            } else {
+                mode.label().to_string()
+            };
+            if root_ui().button(vec2(x, 35. + index as f32 * 25.), label) && *mode != settings.mode {
+                settings.mode = *mode;
+                changed = true;
+                if let Err(e) = settings.save(SETTINGS_FILE) {
+                    eprintln!("could not save settings: {}", e);
+                }
+            }
+        }
+        changed
+    }
+
+    // Takes the place of the mode buttons during a network game.
+    fn draw_lan_panel(session: &LanSession) {
+        let x = screen_width() - 110.;
+        let color = if session.color > 0 { "black" } else { "red" };
+        let lines = [
+            match session.role {
+                LanRole::Host => "Hosting".to_string(),
+                LanRole::Guest => "Joined".to_string(),
+            },
+            session.address.clone(),
+            match (session.status, session.role) {
+                (LanStatus::Waiting, LanRole::Host) => "Waiting for a player".to_string(),
+                (LanStatus::Waiting, LanRole::Guest) => "Connecting...".to_string(),
+                (LanStatus::Connected, _) => "Connected".to_string(),
+                (LanStatus::Disconnected, LanRole::Host) => "Lost the player, waiting".to_string(),
+                (LanStatus::Disconnected, LanRole::Guest) => "Lost the host, retrying".to_string(),
+            },
+            if session.color == 0 { String::new() } else { format!("You play {}", color) },
+            if session.can_move() { "Your move".to_string() } else { String::new() },
+        ];
+        for (index, line) in lines.iter().enumerate() {
+            root_ui().label(vec2(x, 10. + index as f32 * 20.), line);
+        }
+    }
+
+    // Progress through the puzzles file. Returns true when the player asks for
+    // the next puzzle.
+    fn draw_puzzle_panel(session: Option<&PuzzleSession>, number: usize, total: usize) -> bool {
+        let x = screen_width() - 110.;
+        let y = 35. + GameMode::ALL.len() as f32 * 25. + 10.;
+        let Some(session) = session else {
+            root_ui().label(vec2(x, y), "No puzzles in");
+            root_ui().label(vec2(x, y + 20.), PUZZLES_FILE);
+            return false;
+        };
+        let lines = [
+            format!("Puzzle {}/{}", number % total + 1, total),
+            if session.is_solved() { "Solved!".to_string() } else { format!("{} to play", session.puzzle.side_to_move()) },
+            if session.is_solved() { String::new() } else { "and win material".to_string() },
+            format!("{} mistakes", session.mistakes),
+        ];
+        for (index, line) in lines.iter().enumerate() {
+            root_ui().label(vec2(x, y + index as f32 * 20.), line);
+        }
+        root_ui().button(vec2(x, y + lines.len() as f32 * 20. + 5.), "Next puzzle")
+    }
+
     fn paint_board () {
         let game_size = screen_width().min(screen_height());
         let offset_x = (screen_width() - game_size) / 2. + 10.;
@@ -0,0 +1,274 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::evaluator::{MaterialEvaluator, WeightedEvaluator};
+use crate::game::{play_game_from, Game, GameResult};
+use crate::mcts::{MctsPlayer, PlayoutPolicy};
+use crate::nn::NeuralEvaluator;
+use crate::player::{AlphaBetaPlayer, GreedyPlayer, Player, RandomPlayer};
+use crate::search::SearchOptions;
+
+// An engine configuration written as `kind:key=value,key=value`, e.g.
+// `ab:depth=5,weights=weights.txt`, `ab:depth=5,nn=nn.txt`, `mcts:playouts=2000,policy=greedy`,
+// `greedy` or `random`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineSpec {
+    pub kind: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl EngineSpec {
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn parsed<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        match self.param(key) {
+            Some(value) => value.parse().map_err(|_| format!("bad value for {}: {}", key, value)),
+            None => Ok(default),
+        }
+    }
+
+    pub fn build(&self, seed: u64) -> Result<Box<dyn Player>, String> {
+        match self.kind.as_str() {
+            "random" => Ok(Box::new(RandomPlayer::with_seed(seed))),
+            "greedy" => Ok(Box::new(GreedyPlayer::with_seed(seed))),
+            "ab" => {
+                let options = SearchOptions {
+                    max_depth: self.parsed("depth", 5)?,
+                    time_budget: self.param("time").map(|t| t.parse().map(Duration::from_millis))
+                        .transpose().map_err(|_| "bad value for time".to_string())?,
+                    eval_noise: self.parsed("noise", 0)?,
+                    blunder_chance: self.parsed("blunder", 0.)?,
+                    multi_pv: self.parsed("multipv", 1)?,
+                    pv_margin: self.parsed("margin", 0)?,
+                    threads: self.parsed("threads", 1)?,
+                    deterministic_seed: self.param("seed").map(|v| v.parse()).transpose()
+                        .map_err(|_| "bad value for seed".to_string())?,
+                    tablebase: None,
+                    book: None,
+                };
+                if let Some(path) = self.param("nn") {
+                    let evaluator = NeuralEvaluator::load(path).map_err(|e| format!("could not load {}: {}", path, e))?;
+                    return Ok(Box::new(AlphaBetaPlayer::with_options(options, Box::new(evaluator))));
+                }
+                let player = match self.param("weights") {
+                    Some("material") => AlphaBetaPlayer::with_options(options, Box::new(MaterialEvaluator)),
+                    Some(path) => {
+                        let evaluator = WeightedEvaluator::load(path).map_err(|e| format!("could not load {}: {}", path, e))?;
+                        AlphaBetaPlayer::with_options(options, Box::new(evaluator))
+                    },
+                    None => AlphaBetaPlayer::with_options(options, Box::new(WeightedEvaluator::default())),
+                };
+                Ok(Box::new(player))
+            },
+            "mcts" => {
+                let mut player = MctsPlayer::with_seed(self.parsed("playouts", 2000)?, seed);
+                player.exploration = self.parsed("c", player.exploration)?;
+                player.policy = match self.param("policy") {
+                    Some("greedy") => PlayoutPolicy::Greedy,
+                    Some("random") | None => PlayoutPolicy::Random,
+                    Some(other) => return Err(format!("unknown playout policy {}", other)),
+                };
+                Ok(Box::new(player))
+            },
+            other => Err(format!("unknown engine kind {}", other)),
+        }
+    }
+}
+
+impl FromStr for EngineSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = s.split_once(':').unwrap_or((s, ""));
+        let params = rest.split(',').filter(|p| !p.is_empty()).map(|p| {
+            p.split_once('=')
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                .ok_or_else(|| format!("expected key=value, got {}", p))
+        }).collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { kind: kind.trim().to_string(), params })
+    }
+}
+
+impl fmt::Display for EngineSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        for (n, (key, value)) in self.params.iter().enumerate() {
+            write!(f, "{}{}={}", if n == 0 { ":" } else { "," }, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+// Every position after two plies from the start, used in turn so the games
+// of a match are not all the same.
+pub fn two_ply_openings() -> Vec<Game> {
+    let start = Game::new();
+    let mut openings = Vec::new();
+    for (j, i, m) in start.legal_moves() {
+        let mut first = start.clone();
+        first.play(j, i, &m).expect("legal opening move");
+        for (rj, ri, rm) in first.legal_moves() {
+            let mut second = first.clone();
+            second.play(rj, ri, &rm).expect("legal reply");
+            openings.push(second);
+        }
+    }
+    openings
+}
+
+// Win/draw/loss counts from the first engine's point of view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+fn elo_from_score(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1. - 1e-6);
+    -400. * (1. / score - 1.).log10()
+}
+
+fn score_from_elo(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.) / self.games().max(1) as f64
+    }
+
+    // Elo difference and the half width of its 95% confidence interval.
+    pub fn elo(&self) -> (f64, f64) {
+        let n = self.games().max(1) as f64;
+        let s = self.score();
+        let variance = (self.wins as f64 * (1. - s).powi(2)
+            + self.draws as f64 * (0.5 - s).powi(2)
+            + self.losses as f64 * s.powi(2)) / n;
+        let margin = 1.96 * (variance / n).sqrt();
+        let low = elo_from_score(s - margin);
+        let high = elo_from_score(s + margin);
+        (elo_from_score(s), (high - low) / 2.)
+    }
+
+    // Log likelihood ratio of H1 (elo1) against H0 (elo0), using the normal
+    // approximation to the trinomial model. Each outcome gets half a game of
+    // prior weight, so one-sided or drawless results still have a variance.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let n = self.games() as f64;
+        if n == 0. {
+            return 0.;
+        }
+        let total = n + 1.5;
+        let w = (self.wins as f64 + 0.5) / total;
+        let d = (self.draws as f64 + 0.5) / total;
+        let s = w + d / 2.;
+        let variance = (w + d / 4. - s * s) / n;
+        if variance <= 0. {
+            return 0.;
+        }
+        let s0 = score_from_elo(elo0);
+        let s1 = score_from_elo(elo1);
+        (s1 - s0) * (2. * s - s0 - s1) / (2. * variance)
+    }
+
+    pub fn record(&mut self, result: GameResult, first_is_black: bool) {
+        match (result, first_is_black) {
+            (GameResult::Draw, _) => self.draws += 1,
+            (GameResult::BlackWon, true) | (GameResult::RedWon, false) => self.wins += 1,
+            _ => self.losses += 1,
+        }
+    }
+}
+
+impl fmt::Display for MatchScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (elo, error) = self.elo();
+        write!(f, "+{} ={} -{} ({:.1}%), elo {:+.1} +/- {:.1}", self.wins, self.draws, self.losses, self.score() * 100., elo, error)
+    }
+}
+
+// Sequential probability ratio test between elo0 (H0) and elo1 (H1) with
+// false positive rate alpha and false negative rate beta.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtResult {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+impl Sprt {
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1. - self.alpha)).ln(), ((1. - self.beta) / self.alpha).ln())
+    }
+
+    pub fn check(&self, score: &MatchScore) -> SprtResult {
+        let llr = score.llr(self.elo0, self.elo1);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtResult::AcceptH1
+        } else if llr <= lower {
+            SprtResult::AcceptH0
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+pub struct MatchConfig {
+    pub first: EngineSpec,
+    pub second: EngineSpec,
+    // Rounded up to whole pairs, since every opening is played with both colors.
+    pub games: usize,
+    pub max_plies: usize,
+    pub sprt: Option<Sprt>,
+}
+
+fn play_one(opening: &Game, black: &EngineSpec, red: &EngineSpec, seed: u64, max_plies: usize) -> Result<GameResult, String> {
+    let mut black_player = black.build(seed)?;
+    let mut red_player = red.build(seed.wrapping_add(1))?;
+    let (result, _) = play_game_from(opening.clone(), black_player.as_mut(), red_player.as_mut(), max_plies)
+        .map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+// Plays game pairs from successive openings, swapping colors within each
+// pair, and stops early once the SPRT (if any) reaches a decision. `report`
+// is called after every game.
+pub fn run_match(config: &MatchConfig, mut report: impl FnMut(&MatchScore)) -> Result<(MatchScore, Option<SprtResult>), String> {
+    let openings = two_ply_openings();
+    let mut score = MatchScore::default();
+    let pairs = config.games.div_ceil(2);
+    for pair in 0..pairs {
+        let opening = &openings[pair % openings.len()];
+        let seed = pair as u64 * 2;
+        let result = play_one(opening, &config.first, &config.second, seed, config.max_plies)?;
+        score.record(result, true);
+        report(&score);
+        let result = play_one(opening, &config.second, &config.first, seed + 1, config.max_plies)?;
+        score.record(result, false);
+        report(&score);
+        if let Some(sprt) = config.sprt {
+            let decision = sprt.check(&score);
+            if decision != SprtResult::Continue {
+                return Ok((score, Some(decision)));
+            }
+        }
+    }
+    Ok((score, config.sprt.map(|sprt| sprt.check(&score))))
+}
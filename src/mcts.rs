@@ -0,0 +1,198 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rayon::prelude::*;
+
+use crate::checkers::{Checkers, CheckersError, Move};
+use crate::game::Game;
+use crate::player::{captured_count, Player};
+
+// How moves are picked during the random playout from a new leaf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayoutPolicy {
+    Random,
+    // Prefer the move capturing the most pieces, otherwise play randomly.
+    Greedy,
+}
+
+// UCT Monte Carlo tree search. The playouts are split across `threads`
+// independent trees (root parallelization) whose root statistics are summed
+// before picking the most visited move.
+pub struct MctsPlayer {
+    pub playouts: usize,
+    pub exploration: f64,
+    pub policy: PlayoutPolicy,
+    // Playouts longer than this are scored on material instead of played out.
+    pub max_playout_moves: usize,
+    pub threads: usize,
+    rng: StdRng,
+}
+
+struct Node {
+    board: Checkers,
+    from_parent: Option<(usize, usize, Move)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<(usize, usize, Move)>,
+    visits: f64,
+    // Total reward for the side that made the move leading to this node.
+    wins: f64,
+}
+
+impl Node {
+    fn new(board: Checkers, from_parent: Option<(usize, usize, Move)>, parent: Option<usize>) -> Self {
+        Self {
+            untried: board.get_all_moves(),
+            board,
+            from_parent,
+            parent,
+            children: Vec::new(),
+            visits: 0.,
+            wins: 0.,
+        }
+    }
+}
+
+// Statistics of one root move after the search.
+#[derive(Debug, Clone)]
+pub struct RootMoveStats {
+    pub mv: (usize, usize, Move),
+    pub visits: f64,
+    pub wins: f64,
+}
+
+impl MctsPlayer {
+    pub fn new(playouts: usize) -> Self {
+        Self::with_rng(playouts, StdRng::from_entropy())
+    }
+
+    pub fn with_seed(playouts: usize, seed: u64) -> Self {
+        Self::with_rng(playouts, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(playouts: usize, rng: StdRng) -> Self {
+        Self {
+            playouts,
+            exploration: std::f64::consts::SQRT_2,
+            policy: PlayoutPolicy::Random,
+            max_playout_moves: 150,
+            threads: rayon::current_num_threads(),
+            rng,
+        }
+    }
+
+    // Visit counts and rewards of every root move, summed across trees.
+    pub fn search(&mut self, board: &Checkers) -> Vec<RootMoveStats> {
+        let threads = self.threads.max(1);
+        let seeds: Vec<u64> = (0..threads).map(|_| self.rng.next_u64()).collect();
+        let per_tree = self.playouts.div_ceil(threads);
+        let trees: Vec<Vec<RootMoveStats>> = seeds
+            .into_par_iter()
+            .map(|seed| self.run_tree(board, per_tree, seed))
+            .collect();
+
+        let mut totals: Vec<RootMoveStats> = Vec::new();
+        for tree in trees {
+            for stats in tree {
+                match totals.iter_mut().find(|total| total.mv == stats.mv) {
+                    Some(total) => {
+                        total.visits += stats.visits;
+                        total.wins += stats.wins;
+                    },
+                    None => totals.push(stats),
+                }
+            }
+        }
+        totals
+    }
+
+    fn run_tree(&self, board: &Checkers, playouts: usize, seed: u64) -> Vec<RootMoveStats> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut tree = vec![Node::new(*board, None, None)];
+        for _ in 0..playouts {
+            // selection
+            let mut current = 0;
+            while tree[current].untried.is_empty() && !tree[current].children.is_empty() {
+                current = self.select_child(&tree, current);
+            }
+            // expansion
+            if !tree[current].untried.is_empty() {
+                let index = rng.gen_range(0..tree[current].untried.len());
+                let (j, i, m) = tree[current].untried.swap_remove(index);
+                let mut child_board = tree[current].board;
+                child_board.make_move_from_enum(j, i, &m).expect("move from enum failed");
+                tree.push(Node::new(child_board, Some((j, i, m)), Some(current)));
+                let child = tree.len() - 1;
+                tree[current].children.push(child);
+                current = child;
+            }
+            // simulation, scored 1 for a black win and 0 for a red win
+            let black_reward = self.playout(tree[current].board, &mut rng);
+            // backpropagation
+            let mut node = Some(current);
+            while let Some(n) = node {
+                let mover = -tree[n].board.current_turn;
+                tree[n].visits += 1.;
+                tree[n].wins += if mover > 0 { black_reward } else { 1. - black_reward };
+                node = tree[n].parent;
+            }
+        }
+        tree[0].children.iter().map(|&c| RootMoveStats {
+            mv: tree[c].from_parent.clone().expect("child without a move"),
+            visits: tree[c].visits,
+            wins: tree[c].wins,
+        }).collect()
+    }
+
+    fn select_child(&self, tree: &[Node], parent: usize) -> usize {
+        let log_visits = tree[parent].visits.ln();
+        let mut best = tree[parent].children[0];
+        let mut best_value = f64::MIN;
+        for &c in &tree[parent].children {
+            let child = &tree[c];
+            let value = child.wins / child.visits + self.exploration * (log_visits / child.visits).sqrt();
+            if value > best_value {
+                best_value = value;
+                best = c;
+            }
+        }
+        best
+    }
+
+    fn playout(&self, mut board: Checkers, rng: &mut StdRng) -> f64 {
+        for _ in 0..self.max_playout_moves {
+            let moves = board.get_all_moves();
+            if moves.is_empty() {
+                return if board.current_turn > 0 { 0. } else { 1. };
+            }
+            let chosen = match self.policy {
+                PlayoutPolicy::Random => moves.choose(rng),
+                PlayoutPolicy::Greedy => {
+                    let most = moves.iter().map(|(_, _, m)| captured_count(m)).max().unwrap_or(0);
+                    let best: Vec<&(usize, usize, Move)> = moves.iter().filter(|(_, _, m)| captured_count(m) == most).collect();
+                    best.choose(rng).copied()
+                },
+            };
+            let (j, i, m) = chosen.expect("moves were not empty");
+            board.make_move_from_enum(*j, *i, m).expect("move from enum failed");
+        }
+        match board.count_material() {
+            material if material > 0 => 1.,
+            material if material < 0 => 0.,
+            _ => 0.5,
+        }
+    }
+}
+
+impl Player for MctsPlayer {
+    fn choose_move(&mut self, game: &Game) -> Result<(usize, usize, Move), CheckersError> {
+        let mut best: Option<RootMoveStats> = None;
+        for stats in self.search(&game.checkers) {
+            match &best {
+                Some(current) if current.visits >= stats.visits => (),
+                _ => best = Some(stats),
+            }
+        }
+        best.map(|stats| stats.mv).ok_or(CheckersError::GameOver)
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Who plays which color in the GUI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+    // Two people taking turns at the same board.
+    HotSeat,
+    #[default]
+    HumanBlack,
+    HumanRed,
+    // The engine plays both sides.
+    AiVsAi,
+    // Positions from the puzzles file with a winning line to find.
+    Puzzle,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] = [
+        GameMode::HotSeat,
+        GameMode::HumanBlack,
+        GameMode::HumanRed,
+        GameMode::AiVsAi,
+        GameMode::Puzzle,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::HotSeat => "hot-seat",
+            GameMode::HumanBlack => "human-black",
+            GameMode::HumanRed => "human-red",
+            GameMode::AiVsAi => "ai-vs-ai",
+            GameMode::Puzzle => "puzzle",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::HotSeat => "Two players",
+            GameMode::HumanBlack => "Play black",
+            GameMode::HumanRed => "Play red",
+            GameMode::AiVsAi => "Watch AI",
+            GameMode::Puzzle => "Puzzles",
+        }
+    }
+
+    // Whether the engine plays the side with this `current_turn` sign.
+    // Puzzle replies are scripted, not searched.
+    pub fn is_ai(&self, current_turn: i32) -> bool {
+        match self {
+            GameMode::HotSeat | GameMode::Puzzle => false,
+            GameMode::HumanBlack => current_turn < 0,
+            GameMode::HumanRed => current_turn > 0,
+            GameMode::AiVsAi => true,
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GameMode::ALL
+            .iter()
+            .find(|m| m.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| format!("unknown game mode {}", s.trim()))
+    }
+}
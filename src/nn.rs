@@ -0,0 +1,312 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::checkers::Checkers;
+use crate::evaluator::Evaluator;
+use crate::pdn::square_coords;
+use crate::tuning::TrainingPosition;
+
+pub const NN_FILE: &str = "nn.txt";
+const HEADER: &str = "checkers-nn 1";
+
+// One input per square for men and one per square for kings, +1 for a black
+// piece and -1 for a red one.
+pub const INPUTS: usize = 64;
+pub const DEFAULT_HIDDEN: [usize; 2] = [32, 16];
+
+// Evaluation points per unit of the network's output, which is the log odds
+// of black winning. Ten keeps a man's worth close to `MaterialEvaluator`'s.
+const SCORE_SCALE: f32 = 10.;
+
+pub fn encode(board: &Checkers) -> [f32; INPUTS] {
+    let mut input = [0.; INPUTS];
+    for number in 1..=32 {
+        let (j, i) = square_coords(number).expect("valid square");
+        let piece = board.board_state[j][i];
+        if piece != 0 {
+            let plane = if piece.abs() == 3 { 32 } else { 0 };
+            input[plane + number - 1] = piece.signum() as f32;
+        }
+    }
+    input
+}
+
+// The same position seen from the other side: turned around with the colors
+// swapped.
+fn mirrored(position: &TrainingPosition) -> TrainingPosition {
+    let mut board = position.board;
+    for j in 0..8 {
+        for i in 0..8 {
+            board.board_state[j][i] = -position.board.board_state[7 - j][7 - i];
+        }
+    }
+    board.current_turn = -position.board.current_turn;
+    TrainingPosition { board, outcome: 1. - position.outcome }
+}
+
+// A fully connected layer; `weights` holds one row of `inputs` values per
+// output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub weights: Vec<f32>,
+    pub biases: Vec<f32>,
+}
+
+impl Layer {
+    fn zeroed(inputs: usize, outputs: usize) -> Self {
+        Self { inputs, outputs, weights: vec![0.; inputs * outputs], biases: vec![0.; outputs] }
+    }
+
+    fn forward(&self, input: &[f32], relu: bool) -> Vec<f32> {
+        self.weights.chunks_exact(self.inputs).zip(&self.biases).map(|(row, bias)| {
+            let sum = row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>() + bias;
+            if relu { sum.max(0.) } else { sum }
+        }).collect()
+    }
+
+    fn add_scaled(&mut self, other: &Layer, scale: f32) {
+        for (w, g) in self.weights.iter_mut().zip(&other.weights) {
+            *w += scale * g;
+        }
+        for (b, g) in self.biases.iter_mut().zip(&other.biases) {
+            *b += scale * g;
+        }
+    }
+}
+
+// A small multilayer perceptron: ReLU hidden layers and one linear output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    pub layers: Vec<Layer>,
+}
+
+impl Network {
+    // Random He-style initial weights for `INPUTS -> hidden... -> 1`.
+    pub fn new(hidden: &[usize], seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sizes = vec![INPUTS];
+        sizes.extend_from_slice(hidden);
+        sizes.push(1);
+        let layers = sizes.windows(2).map(|pair| {
+            let mut layer = Layer::zeroed(pair[0], pair[1]);
+            let range = (6. / pair[0] as f32).sqrt();
+            for w in layer.weights.iter_mut() {
+                *w = rng.gen_range(-range..range);
+            }
+            layer
+        }).collect();
+        Self { layers }
+    }
+
+    // Log odds of black winning.
+    pub fn forward(&self, input: &[f32]) -> f32 {
+        let mut activation = input.to_vec();
+        for (n, layer) in self.layers.iter().enumerate() {
+            activation = layer.forward(&activation, n + 1 < self.layers.len());
+        }
+        activation[0]
+    }
+
+    fn zeroed_like(&self) -> Vec<Layer> {
+        self.layers.iter().map(|layer| Layer::zeroed(layer.inputs, layer.outputs)).collect()
+    }
+
+    // Adds the cross-entropy gradient for one example to `gradients` and
+    // returns the example's loss.
+    fn backpropagate(&self, input: &[f32], target: f32, gradients: &mut [Layer]) -> f32 {
+        let mut activations = vec![input.to_vec()];
+        for (n, layer) in self.layers.iter().enumerate() {
+            let next = layer.forward(activations.last().expect("input layer"), n + 1 < self.layers.len());
+            activations.push(next);
+        }
+        let prediction = sigmoid(activations[self.layers.len()][0]);
+        let mut delta = vec![prediction - target];
+        for (n, layer) in self.layers.iter().enumerate().rev() {
+            let below = &activations[n];
+            let gradient = &mut gradients[n];
+            for (o, d) in delta.iter().enumerate() {
+                gradient.biases[o] += d;
+                for (g, x) in gradient.weights[o * layer.inputs..(o + 1) * layer.inputs].iter_mut().zip(below) {
+                    *g += d * x;
+                }
+            }
+            if n > 0 {
+                delta = (0..layer.inputs).map(|k| {
+                    if below[k] <= 0. {
+                        return 0.;
+                    }
+                    delta.iter().enumerate().map(|(o, d)| d * layer.weights[o * layer.inputs + k]).sum()
+                }).collect();
+            }
+        }
+        let p = prediction.clamp(1e-6, 1. - 1e-6);
+        -(target * p.ln() + (1. - target) * (1. - p).ln())
+    }
+
+    // Text format: a header line, the layer sizes, then for each layer its
+    // weights row by row followed by its biases, one row per line.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut contents = format!("{}\n", HEADER);
+        let mut sizes = vec![INPUTS.to_string()];
+        sizes.extend(self.layers.iter().map(|layer| layer.outputs.to_string()));
+        contents.push_str(&sizes.join(" "));
+        contents.push('\n');
+        for layer in &self.layers {
+            for row in layer.weights.chunks_exact(layer.inputs) {
+                contents.push_str(&join_floats(row));
+                contents.push('\n');
+            }
+            contents.push_str(&join_floats(&layer.biases));
+            contents.push('\n');
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut lines = contents.lines();
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(invalid("not a network file"));
+        }
+        let sizes: Vec<usize> = lines.next().ok_or_else(|| invalid("missing layer sizes"))?
+            .split_whitespace()
+            .map(|size| size.parse().map_err(|_| invalid("bad layer size")))
+            .collect::<io::Result<_>>()?;
+        if sizes.len() < 2 || sizes[0] != INPUTS || sizes[sizes.len() - 1] != 1 {
+            return Err(invalid("unsupported layer sizes"));
+        }
+        let mut read_row = |length: usize| -> io::Result<Vec<f32>> {
+            let row: Vec<f32> = lines.next().ok_or_else(|| invalid("truncated network file"))?
+                .split_whitespace()
+                .map(|value| value.parse().map_err(|_| invalid("bad weight")))
+                .collect::<io::Result<_>>()?;
+            if row.len() == length { Ok(row) } else { Err(invalid("wrong row length")) }
+        };
+        let mut layers = Vec::new();
+        for pair in sizes.windows(2) {
+            let mut layer = Layer::zeroed(pair[0], pair[1]);
+            layer.weights.clear();
+            for _ in 0..pair[1] {
+                layer.weights.extend(read_row(pair[0])?);
+            }
+            layer.biases = read_row(pair[1])?;
+            layers.push(layer);
+        }
+        Ok(Self { layers })
+    }
+}
+
+fn join_floats(values: &[f32]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1. / (1. + (-x).exp())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainingOptions {
+    pub epochs: usize,
+    pub learning_rate: f32,
+    pub momentum: f32,
+    pub batch_size: usize,
+    // Share of the positions held back to measure the loss on unseen data.
+    pub validation_share: f64,
+    pub seed: u64,
+}
+
+impl Default for TrainingOptions {
+    fn default() -> Self {
+        Self {
+            epochs: 30,
+            learning_rate: 0.05,
+            momentum: 0.9,
+            batch_size: 256,
+            validation_share: 0.1,
+            seed: 0,
+        }
+    }
+}
+
+// Minibatch gradient descent with momentum on the cross-entropy between the
+// predicted chance of black winning and each game's outcome. Every position
+// is also used mirrored, which teaches the network that the colors are
+// symmetric. `report` gets the epoch and the training and validation losses.
+pub fn train(network: &mut Network, positions: &[TrainingPosition], options: &TrainingOptions, mut report: impl FnMut(usize, f32, f32)) {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut examples: Vec<([f32; INPUTS], f32)> = positions.iter()
+        .flat_map(|position| [*position, mirrored(position)])
+        .map(|position| (encode(&position.board), position.outcome as f32))
+        .collect();
+    examples.shuffle(&mut rng);
+    let validation_len = (examples.len() as f64 * options.validation_share) as usize;
+    let (validation, training) = examples.split_at_mut(validation_len);
+
+    let mut velocity = network.zeroed_like();
+    for epoch in 0..options.epochs {
+        training.shuffle(&mut rng);
+        let mut total_loss = 0.;
+        for batch in training.chunks(options.batch_size.max(1)) {
+            let (gradients, loss) = batch.par_chunks(32).map(|chunk| {
+                let mut gradients = network.zeroed_like();
+                let loss: f32 = chunk.iter().map(|(input, target)| network.backpropagate(input, *target, &mut gradients)).sum();
+                (gradients, loss)
+            }).reduce(|| (network.zeroed_like(), 0.), |(mut a, loss_a), (b, loss_b)| {
+                for (layer, other) in a.iter_mut().zip(&b) {
+                    layer.add_scaled(other, 1.);
+                }
+                (a, loss_a + loss_b)
+            });
+            total_loss += loss;
+            for ((layer, speed), gradient) in network.layers.iter_mut().zip(velocity.iter_mut()).zip(&gradients) {
+                for v in speed.weights.iter_mut().chain(speed.biases.iter_mut()) {
+                    *v *= options.momentum;
+                }
+                speed.add_scaled(gradient, -options.learning_rate / batch.len() as f32);
+                layer.add_scaled(speed, 1.);
+            }
+        }
+        let validation_loss = validation.par_iter().map(|(input, target)| {
+            let p = sigmoid(network.forward(input)).clamp(1e-6, 1. - 1e-6);
+            -(target * p.ln() + (1. - target) * (1. - p).ln())
+        }).sum::<f32>() / validation.len().max(1) as f32;
+        report(epoch + 1, total_loss / training.len().max(1) as f32, validation_loss);
+    }
+}
+
+// Evaluates with a trained network. The network is shared, so clones are
+// cheap and can be handed to search threads.
+#[derive(Debug, Clone)]
+pub struct NeuralEvaluator {
+    network: Arc<Network>,
+}
+
+impl NeuralEvaluator {
+    pub fn new(network: Network) -> Self {
+        Self { network: Arc::new(network) }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(Network::load(path)?))
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+}
+
+impl Evaluator for NeuralEvaluator {
+    fn evaluate(&self, board: &Checkers) -> i32 {
+        (self.network.forward(&encode(board)) * SCORE_SCALE).round() as i32
+    }
+}
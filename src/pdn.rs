@@ -0,0 +1,293 @@
+use crate::checkers::{Checkers, Move};
+use crate::game::{Game, GameResult};
+
+// Standard checkers numbering: black's back row is 1-4 and red's is 29-32.
+// Black sits at the bottom of our board, so square 1 is at (7, 6).
+pub fn square_number(j: usize, i: usize) -> usize {
+    let row = 7 - j;
+    let column = 7 - i;
+    4 * row + column / 2 + 1
+}
+
+pub fn square_coords(number: usize) -> Option<(usize, usize)> {
+    if !(1..=32).contains(&number) {
+        return None;
+    }
+    let row = (number - 1) / 4;
+    let k = (number - 1) % 4;
+    let column = 2 * k + (row + 1) % 2;
+    Some((7 - row, 7 - column))
+}
+
+// "11-15" for a step, "15x22x29" for captures with every landing square.
+pub fn move_to_notation(j: usize, i: usize, m: &Move) -> String {
+    let separator = if m.is_jump() { "x" } else { "-" };
+    m.path(j, i)
+        .iter()
+        .map(|(pj, pi)| square_number(*pj, *pi).to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+// Squares named in a move like "11-15" or "15x22x29".
+pub fn parse_squares(text: &str) -> Option<Vec<(usize, usize)>> {
+    let squares: Option<Vec<(usize, usize)>> = text
+        .trim()
+        .split(['-', 'x', 'X'])
+        .map(|part| part.parse().ok().and_then(square_coords))
+        .collect();
+    squares.filter(|s| s.len() >= 2)
+}
+
+// Finds the legal move whose path matches the given squares. The start and
+// end squares must match; any squares in between must be on the path in
+// order. Ambiguous or illegal moves give None.
+pub fn find_move_by_squares(board: &Checkers, squares: &[(usize, usize)]) -> Option<(usize, usize, Move)> {
+    if squares.len() < 2 {
+        return None;
+    }
+    let matches: Vec<_> = board.get_all_moves().into_iter().filter(|(j, i, m)| {
+        let path = m.path(*j, *i);
+        if path[0] != squares[0] || path[path.len() - 1] != squares[squares.len() - 1] {
+            return false;
+        }
+        let mut rest = path.iter();
+        squares.iter().all(|square| rest.any(|p| p == square))
+    }).collect();
+    if matches.len() == 1 {
+        matches.into_iter().next()
+    } else {
+        None
+    }
+}
+
+pub fn parse_move(board: &Checkers, text: &str) -> Option<(usize, usize, Move)> {
+    find_move_by_squares(board, &parse_squares(text)?)
+}
+
+// The standard starting position.
+pub const START_FEN: &str = "B:W21-32:B1-12";
+
+// PDN FEN such as "B:W21,22,K30:B1,2,3": side to move, then red (white in
+// PDN) and black piece lists with K marking kings.
+pub fn to_fen(board: &Checkers) -> String {
+    let mut red = Vec::new();
+    let mut black = Vec::new();
+    for number in 1..=32 {
+        let (j, i) = square_coords(number).expect("valid square");
+        let piece = board.board_state[j][i];
+        let entry = if piece.abs() == 3 { format!("K{}", number) } else { number.to_string() };
+        if piece > 0 {
+            black.push(entry);
+        } else if piece < 0 {
+            red.push(entry);
+        }
+    }
+    let side = if board.current_turn > 0 { "B" } else { "W" };
+    format!("{}:W{}:B{}", side, red.join(","), black.join(","))
+}
+
+pub fn from_fen(text: &str) -> Option<Checkers> {
+    let mut sections = text.trim().trim_matches('"').split(':');
+    let current_turn = match sections.next()?.trim() {
+        "B" => 1,
+        "W" => -1,
+        _ => return None,
+    };
+    let mut board_state = [[0; 8]; 8];
+    for section in sections {
+        let section = section.trim();
+        let color = match section.chars().next()? {
+            'B' => 1,
+            'W' => -1,
+            _ => return None,
+        };
+        for entry in section[1..].split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (king, squares) = match entry.strip_prefix('K') {
+                Some(rest) => (true, rest),
+                None => (false, entry),
+            };
+            // ranges like "1-12" are allowed by the format
+            let (first, last) = match squares.split_once('-') {
+                Some((a, b)) => (a.parse().ok()?, b.parse().ok()?),
+                None => (squares.parse().ok()?, squares.parse().ok()?),
+            };
+            for number in first..=last {
+                let (j, i) = square_coords(number)?;
+                board_state[j][i] = if king { 3 * color } else { color };
+            }
+        }
+    }
+    Some(Checkers::from_position(board_state, current_turn))
+}
+
+// The result token, read with the first number belonging to black, the side
+// that moves first.
+pub fn result_to_string(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::BlackWon) => "1-0",
+        Some(GameResult::RedWon) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+fn parse_result(token: &str) -> Option<Option<GameResult>> {
+    match token {
+        "1-0" | "2-0" => Some(Some(GameResult::BlackWon)),
+        "0-1" | "0-2" => Some(Some(GameResult::RedWon)),
+        "1/2-1/2" | "1-1" => Some(Some(GameResult::Draw)),
+        "*" => Some(None),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PdnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub result: Option<GameResult>,
+}
+
+impl PdnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    // Records a game, with a FEN tag if it did not start from the standard
+    // position.
+    pub fn from_game(game: &Game) -> Self {
+        let mut tags = Vec::new();
+        let start = from_fen(START_FEN).expect("valid start position");
+        if game.start.board_state != start.board_state || game.start.current_turn != start.current_turn {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), to_fen(&game.start)));
+        }
+        let mut board = game.start;
+        let moves = game.history.iter().map(|(j, i, m)| {
+            let notation = move_to_notation(*j, *i, m);
+            board.make_move_from_enum(*j, *i, m).expect("recorded move failed");
+            notation
+        }).collect();
+        Self { tags, moves, result: game.result() }
+    }
+
+    pub fn to_pdn(&self) -> String {
+        self.to_annotated_pdn(&[])
+    }
+
+    // Writes the game with `annotations[n]`, if present and not empty, right
+    // after the nth move, e.g. "?? {11-15 was better}".
+    pub fn to_annotated_pdn(&self, annotations: &[String]) -> String {
+        let mut text: String = self.tags.iter()
+            .map(|(name, value)| format!("[{} \"{}\"]\n", name, value))
+            .collect();
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        let black_first = self.tag("FEN").and_then(from_fen).is_none_or(|board| board.current_turn > 0);
+        let offset = if black_first { 0 } else { 1 };
+        let mut tokens = Vec::new();
+        for (index, notation) in self.moves.iter().enumerate() {
+            let ply = index + offset;
+            if (ply + 1) % 2 == 1 {
+                tokens.push(format!("{}.", ply / 2 + 1));
+            } else if index == 0 {
+                tokens.push(format!("{}...", ply / 2 + 1));
+            }
+            tokens.push(notation.clone());
+            if let Some(annotation) = annotations.get(index).filter(|a| !a.is_empty()) {
+                tokens.push(annotation.clone());
+            }
+        }
+        tokens.push(result_to_string(self.result).to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() + 1 > 79 {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        text.push_str(&line);
+        text.push('\n');
+        text
+    }
+
+    // Replays the moves from the starting position (or the FEN tag), stopping
+    // at the first one that is not legal.
+    pub fn replay(&self) -> Game {
+        let mut game = match self.tag("FEN").and_then(from_fen) {
+            Some(board) => Game::from_position(board),
+            None => Game::new(),
+        };
+        for text in &self.moves {
+            match parse_move(&game.checkers, text) {
+                Some((j, i, m)) => {
+                    if game.play(j, i, &m).is_err() {
+                        break;
+                    }
+                },
+                None => break,
+            }
+        }
+        game
+    }
+}
+
+fn finish_token(token: &mut String, current: &mut PdnGame, games: &mut Vec<PdnGame>) {
+    if token.is_empty() {
+        return;
+    }
+    if let Some(result) = parse_result(token) {
+        current.result = result;
+        games.push(std::mem::take(current));
+    } else {
+        // move numbers may be glued to the move, as in "1.11-15"
+        let candidate = token.rsplit('.').next().unwrap_or_default();
+        if parse_squares(candidate).is_some() {
+            current.moves.push(candidate.to_string());
+        }
+    }
+    token.clear();
+}
+
+// Reads every game in a PDN collection. Tags, comments, move numbers and
+// results are recognised; anything else that is not a move is skipped.
+pub fn parse_pdn(text: &str) -> Vec<PdnGame> {
+    let mut games = Vec::new();
+    let mut current = PdnGame::default();
+    let mut chars = text.chars();
+    let mut token = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                finish_token(&mut token, &mut current, &mut games);
+                if !current.moves.is_empty() {
+                    games.push(std::mem::take(&mut current));
+                }
+                let tag: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if let Some((name, value)) = tag.trim().split_once(' ') {
+                    current.tags.push((name.to_string(), value.trim().trim_matches('"').to_string()));
+                }
+            },
+            '{' => {
+                finish_token(&mut token, &mut current, &mut games);
+                chars.by_ref().take_while(|&c| c != '}').for_each(drop);
+            },
+            c if c.is_whitespace() => finish_token(&mut token, &mut current, &mut games),
+            c => token.push(c),
+        }
+    }
+    finish_token(&mut token, &mut current, &mut games);
+    if !current.moves.is_empty() {
+        games.push(current);
+    }
+    games
+}
@@ -0,0 +1,153 @@
+use std::io::{BufRead, Write};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::checkers::{CheckersError, Move};
+use crate::difficulty::Difficulty;
+use crate::evaluator::{Evaluator, MaterialEvaluator};
+use crate::game::Game;
+use crate::pdn::find_move_by_squares;
+use crate::search::{default_threads, SearchOptions};
+
+// Anything that can pick a move for the side to move: engines and humans alike.
+pub trait Player {
+    fn choose_move(&mut self, game: &Game) -> Result<(usize, usize, Move), CheckersError>;
+}
+
+pub struct RandomPlayer {
+    rng: StdRng,
+}
+
+impl RandomPlayer {
+    pub fn new() -> Self {
+        Self { rng: StdRng::from_entropy() }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Default for RandomPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Player for RandomPlayer {
+    fn choose_move(&mut self, game: &Game) -> Result<(usize, usize, Move), CheckersError> {
+        game.legal_moves().choose(&mut self.rng).cloned().ok_or(CheckersError::GameOver)
+    }
+}
+
+// Takes whichever move captures the most pieces right now, picking randomly
+// between equally good moves.
+pub struct GreedyPlayer {
+    rng: StdRng,
+}
+
+impl GreedyPlayer {
+    pub fn new() -> Self {
+        Self { rng: StdRng::from_entropy() }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Default for GreedyPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn captured_count(m: &Move) -> usize {
+    match m {
+        Move::Sequence(sequence) => sequence.len(),
+        single if single.is_jump() => 1,
+        _ => 0,
+    }
+}
+
+impl Player for GreedyPlayer {
+    fn choose_move(&mut self, game: &Game) -> Result<(usize, usize, Move), CheckersError> {
+        let moves = game.legal_moves();
+        let most = moves.iter().map(|(_, _, m)| captured_count(m)).max().ok_or(CheckersError::GameOver)?;
+        let best: Vec<_> = moves.into_iter().filter(|(_, _, m)| captured_count(m) == most).collect();
+        best.choose(&mut self.rng).cloned().ok_or(CheckersError::GameOver)
+    }
+}
+
+// The alpha-beta engine behind `get_best_move`.
+pub struct AlphaBetaPlayer {
+    pub options: SearchOptions,
+    pub evaluator: Box<dyn Evaluator>,
+}
+
+impl AlphaBetaPlayer {
+    pub fn new(depth: usize) -> Self {
+        Self::with_evaluator(depth, Box::new(MaterialEvaluator))
+    }
+
+    pub fn with_evaluator(depth: usize, evaluator: Box<dyn Evaluator>) -> Self {
+        Self::with_options(SearchOptions::fixed_depth(depth), evaluator)
+    }
+
+    pub fn with_difficulty(difficulty: Difficulty) -> Self {
+        Self::with_options(difficulty.search_options(default_threads()), Box::new(MaterialEvaluator))
+    }
+
+    pub fn with_options(options: SearchOptions, evaluator: Box<dyn Evaluator>) -> Self {
+        Self { options, evaluator }
+    }
+}
+
+impl Player for AlphaBetaPlayer {
+    fn choose_move(&mut self, game: &Game) -> Result<(usize, usize, Move), CheckersError> {
+        let mut board = game.checkers;
+        board.get_best_move_with_options(&self.options, self.evaluator.as_ref())
+    }
+}
+
+// Reads moves as whitespace separated board coordinates: "from_j from_i to_j
+// to_i", optionally with every landing square of a multi-jump in between.
+pub struct HumanPlayer {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl HumanPlayer {
+    pub fn new(input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        Self { input, output }
+    }
+
+    pub fn stdio() -> Self {
+        Self::new(Box::new(std::io::BufReader::new(std::io::stdin())), Box::new(std::io::stdout()))
+    }
+}
+
+impl Player for HumanPlayer {
+    fn choose_move(&mut self, game: &Game) -> Result<(usize, usize, Move), CheckersError> {
+        if game.legal_moves().is_empty() {
+            return Err(CheckersError::GameOver);
+        }
+        loop {
+            write!(self.output, "your move (from_j from_i to_j to_i): ").map_err(|_| CheckersError::GameOver)?;
+            self.output.flush().map_err(|_| CheckersError::GameOver)?;
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+                Ok(0) | Err(_) => return Err(CheckersError::GameOver),
+                Ok(_) => (),
+            }
+            let numbers: Vec<usize> = line.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+            let squares: Vec<(usize, usize)> = numbers.chunks_exact(2).map(|c| (c[0], c[1])).collect();
+            match find_move_by_squares(&game.checkers, &squares) {
+                Some(found) => return Ok(found),
+                None => writeln!(self.output, "{}", CheckersError::ImpossibleMove).map_err(|_| CheckersError::GameOver)?,
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+use crate::checkers::{Checkers, CheckersError, Move};
+use crate::evaluator::MaterialEvaluator;
+use crate::game::Game;
+use crate::pdn::{from_fen, move_to_notation, parse_move, to_fen};
+use crate::search::{multi_pv, side_sign, SearchOptions};
+
+// Where the generator writes puzzles and the GUI reads them.
+pub const PUZZLES_FILE: &str = "puzzles.txt";
+
+// What `MaterialEvaluator` scores a man at. The jumps it also counts are
+// worth less than half a man between them in all but wild positions.
+const MAN: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PuzzleOptions {
+    // Depth of the material search that finds and checks the shots.
+    pub depth: usize,
+    // Material the winning move must gain, in men.
+    pub min_gain: i32,
+    // Longest solution, counting the opponent's replies.
+    pub max_plies: usize,
+}
+
+impl Default for PuzzleOptions {
+    fn default() -> Self {
+        Self {
+            depth: 8,
+            min_gain: 2,
+            max_plies: 5,
+        }
+    }
+}
+
+// A position with exactly one move that wins material, and the line that
+// wins it: the solver's moves with the opponent's replies in between,
+// ending on the solver's last capture.
+#[derive(Clone)]
+pub struct Puzzle {
+    pub board: Checkers,
+    pub solution: Vec<(usize, usize, Move)>,
+}
+
+impl Puzzle {
+    // One line: the FEN, then the solution in move notation.
+    pub fn to_line(&self) -> String {
+        let mut parts = vec![to_fen(&self.board)];
+        parts.extend(self.solution.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)));
+        parts.join(" ")
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        let board = from_fen(parts.next()?)?;
+        let mut position = board;
+        let mut solution = Vec::new();
+        for text in parts {
+            let (j, i, m) = parse_move(&position, text)?;
+            position.make_move_from_enum(j, i, &m).ok()?;
+            solution.push((j, i, m));
+        }
+        if solution.is_empty() {
+            None
+        } else {
+            Some(Self { board, solution })
+        }
+    }
+
+    pub fn side_to_move(&self) -> &'static str {
+        if self.board.current_turn > 0 { "black" } else { "red" }
+    }
+}
+
+pub fn save_puzzles(path: impl AsRef<Path>, puzzles: &[Puzzle]) -> io::Result<()> {
+    let contents: String = puzzles.iter().map(|p| format!("{}\n", p.to_line())).collect();
+    fs::write(path, contents)
+}
+
+pub fn load_puzzles(path: impl AsRef<Path>) -> io::Result<Vec<Puzzle>> {
+    fs::read_to_string(path)?.lines().filter(|l| !l.trim().is_empty()).map(|line| {
+        Puzzle::parse(line).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("bad puzzle line: {}", line)))
+    }).collect()
+}
+
+// The principal variation of the only move reaching `target` material for
+// the side to move, if exactly one does. Scores within half a man count.
+fn only_winning_line(board: &Checkers, target: i32, options: &PuzzleOptions) -> Option<Vec<(usize, usize, Move)>> {
+    if board.get_all_moves().len() < 2 {
+        return None;
+    }
+    let (lines, _) = multi_pv(board, &SearchOptions::fixed_depth(options.depth), &MaterialEvaluator, 2, &AtomicBool::new(false));
+    let target = target * MAN - MAN / 2;
+    match lines.as_slice() {
+        [best, second] if best.score >= target && second.score < target => Some(best.moves.clone()),
+        _ => None,
+    }
+}
+
+// The puzzle in `board`, if it has one. The solver's later moves in the
+// line must each be the only one keeping the gain, or the line stops
+// before them.
+pub fn find_puzzle(board: &Checkers, options: &PuzzleOptions) -> Option<Puzzle> {
+    let target = side_sign(board) * board.count_material() + options.min_gain;
+    let line = only_winning_line(board, target, options)?;
+    let mut position = *board;
+    let mut solution = Vec::new();
+    let mut last_capture = 0;
+    for (ply, (j, i, m)) in line.into_iter().take(options.max_plies).enumerate() {
+        if ply > 0 && ply.is_multiple_of(2) {
+            let only = only_winning_line(&position, target, options);
+            if only.and_then(|l| l.into_iter().next()) != Some((j, i, m.clone())) {
+                break;
+            }
+        }
+        if ply.is_multiple_of(2) && m.is_jump() {
+            last_capture = ply + 1;
+        }
+        position.make_move_from_enum(j, i, &m).ok()?;
+        solution.push((j, i, m));
+    }
+    // a quiet sacrifice whose payoff lies past the line is no puzzle
+    if last_capture == 0 {
+        return None;
+    }
+    solution.truncate(last_capture);
+    Some(Puzzle { board: *board, solution })
+}
+
+// Every puzzle in the positions of `game`, skipping positions already in
+// `seen` and adding the new ones to it.
+pub fn find_puzzles(game: &Game, options: &PuzzleOptions, seen: &mut HashSet<u64>) -> Vec<Puzzle> {
+    let mut board = game.start;
+    let mut puzzles = Vec::new();
+    for (j, i, m) in &game.history {
+        if seen.insert(board.position_hash()) {
+            puzzles.extend(find_puzzle(&board, options));
+        }
+        if board.make_move_from_enum(*j, *i, m).is_err() {
+            break;
+        }
+    }
+    puzzles
+}
+
+// A puzzle being solved in the GUI. Every jump of a multi-capture is
+// checked on its own, so the whole path has to be found.
+#[derive(Clone)]
+pub struct PuzzleSession {
+    pub puzzle: Puzzle,
+    // The position to show, including the jumps made so far of a move in
+    // progress.
+    pub board: Checkers,
+    // The position before the move in progress.
+    position: Checkers,
+    // Solution moves played so far, and jumps of the next one.
+    played: usize,
+    step: usize,
+    pub mistakes: usize,
+}
+
+impl PuzzleSession {
+    pub fn new(puzzle: Puzzle) -> Self {
+        Self {
+            board: puzzle.board,
+            position: puzzle.board,
+            puzzle,
+            played: 0,
+            step: 0,
+            mistakes: 0,
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.played >= self.puzzle.solution.len()
+    }
+
+    fn solver_to_move(&self) -> bool {
+        self.played.is_multiple_of(2)
+    }
+
+    // The solution move the player has to make next.
+    pub fn expected_move(&self) -> Option<(usize, usize, Move)> {
+        if self.solver_to_move() {
+            self.puzzle.solution.get(self.played).cloned()
+        } else {
+            None
+        }
+    }
+
+    // Plays one step of the player's move if it is the next step of the
+    // solution; anything else counts as a mistake and leaves the board as
+    // it was. Putting a piece back where it was is not a try.
+    pub fn try_step(&mut self, from: (usize, usize), to: (usize, usize)) -> Result<(), CheckersError> {
+        let (j, i, m) = self.expected_move().ok_or(CheckersError::ImpossibleMove)?;
+        if from == to {
+            return Err(CheckersError::ImpossibleMove);
+        }
+        let path = m.path(j, i);
+        if path.get(self.step) != Some(&from) || path.get(self.step + 1) != Some(&to) {
+            self.mistakes += 1;
+            return Err(CheckersError::ImpossibleMove);
+        }
+        self.board.make_move(from.0, from.1, to.0, to.1)?;
+        self.step += 1;
+        if self.step + 1 == path.len() {
+            self.finish_move(j, i, &m)?;
+        }
+        Ok(())
+    }
+
+    // Plays the opponent's scripted reply when it is their turn.
+    pub fn reply(&mut self) -> Option<(usize, usize, Move)> {
+        if self.solver_to_move() || self.is_solved() {
+            return None;
+        }
+        let (j, i, m) = self.puzzle.solution[self.played].clone();
+        self.finish_move(j, i, &m).ok()?;
+        Some((j, i, m))
+    }
+
+    fn finish_move(&mut self, j: usize, i: usize, m: &Move) -> Result<(), CheckersError> {
+        self.position.make_move_from_enum(j, i, m)?;
+        self.board = self.position;
+        self.played += 1;
+        self.step = 0;
+        Ok(())
+    }
+}
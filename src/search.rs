@@ -0,0 +1,584 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::book::OpeningBook;
+use crate::checkers::{Checkers, CheckersError, Move};
+use crate::evaluator::Evaluator;
+use crate::pdn::move_to_notation;
+use crate::tablebase::{Tablebase, TbValue};
+use crate::tt::{Bound, TranspositionTable, TtEntry, DEFAULT_TT_ENTRIES};
+
+// Score for the side to move when the opponent has no moves left. The ply is
+// subtracted so that faster wins (and slower losses) are preferred.
+pub const WIN_SCORE: i32 = 1_000_000;
+pub const INFINITY: i32 = i32::MAX;
+
+// A root move with its score for the side to move.
+pub type ScoredMove = (i32, (usize, usize, Move));
+
+// How hard the engine tries and how human its mistakes are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchOptions {
+    pub max_depth: usize,
+    // Deepening stops once this much time has passed; the last completed
+    // depth is used.
+    pub time_budget: Option<Duration>,
+    // Each root move's score is shifted by a random amount in this range.
+    pub eval_noise: i32,
+    // Chance of playing a random move other than the best one.
+    pub blunder_chance: f64,
+    // Root moves the choice is made among. Above one, every root move gets
+    // its true score and the move is picked at random from the best
+    // `multi_pv` that are within `pv_margin` of the best.
+    pub multi_pv: usize,
+    pub pv_margin: i32,
+    // Search threads sharing one transposition table (Lazy SMP).
+    pub threads: usize,
+    // Makes the search reproducible: one thread, no time budget, and noise,
+    // blunders and book choices drawn from this seed mixed with the position.
+    pub deterministic_seed: Option<u64>,
+    // Endgame tablebase probed during the search, if one is loaded.
+    pub tablebase: Option<&'static Tablebase>,
+    // Opening book whose moves are played without searching.
+    pub book: Option<&'static OpeningBook>,
+}
+
+impl SearchOptions {
+    pub fn fixed_depth(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            time_budget: None,
+            eval_noise: 0,
+            blunder_chance: 0.,
+            multi_pv: 1,
+            pv_margin: 0,
+            threads: 1,
+            deterministic_seed: None,
+            tablebase: None,
+            book: None,
+        }
+    }
+
+    pub fn deterministic(self, seed: u64) -> Self {
+        Self {
+            deterministic_seed: Some(seed),
+            ..self
+        }
+    }
+
+    // The time budget actually honoured; deterministic searches ignore it.
+    pub fn time_limit(&self) -> Option<Duration> {
+        match self.deterministic_seed {
+            Some(_) => None,
+            None => self.time_budget,
+        }
+    }
+
+    // The random source for choices about `board`: seeded in deterministic
+    // mode, so the same position always gets the same choices.
+    pub fn rng(&self, board: &Checkers) -> StdRng {
+        match self.deterministic_seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ board.position_hash()),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
+// One search thread per core, for callers that want the whole machine.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// Converts a tablebase distance into a score on the same scale as the
+// search's own mate scores.
+pub fn tablebase_score(value: TbValue, ply: usize) -> i32 {
+    match value {
+        TbValue::Win(plies) => WIN_SCORE - (ply + plies as usize) as i32,
+        TbValue::Loss(plies) => -(WIN_SCORE - (ply + plies as usize) as i32),
+        TbValue::Draw => 0,
+    }
+}
+
+// Positive when black is to move, negative when red is to move.
+pub fn side_sign(board: &Checkers) -> i32 {
+    if board.current_turn > 0 {
+        1
+    } else {
+        -1
+    }
+}
+
+// A root move with its score for the side to move and the line the search
+// expects to follow it, the root move first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    pub score: i32,
+    pub moves: Vec<(usize, usize, Move)>,
+}
+
+impl fmt::Display for PvLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let moves: Vec<String> = self.moves.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect();
+        write!(f, "{:>8} {}", self.score, moves.join(" "))
+    }
+}
+
+// One finished iteration of iterative deepening. Nodes and time are for this
+// iteration alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IterationStats {
+    pub depth: usize,
+    pub score: i32,
+    pub best_move: (usize, usize, Move),
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for IterationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (j, i, m) = &self.best_move;
+        write!(f, "depth {} score {} nodes {} time {}ms best ({}, {}) {:?}", self.depth, self.score, self.nodes, self.elapsed.as_millis(), j, i, m)
+    }
+}
+
+// Counters for a whole search, across all iterations so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub beta_cutoffs: u64,
+    // Cutoffs made by the first move tried, which shows how good the move
+    // ordering is.
+    pub first_move_cutoffs: u64,
+    pub tt_hits: u64,
+    pub tablebase_hits: u64,
+    // Deepest ply reached by any line.
+    pub seldepth: usize,
+    pub elapsed: Duration,
+    pub iterations: Vec<IterationStats>,
+}
+
+impl SearchStats {
+    pub fn nodes_per_second(&self) -> u64 {
+        (self.nodes as f64 / self.elapsed.as_secs_f64().max(1e-6)) as u64
+    }
+
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        self.first_move_cutoffs as f64 / self.beta_cutoffs.max(1) as f64
+    }
+
+    pub fn depth(&self) -> usize {
+        self.iterations.last().map_or(0, |iteration| iteration.depth)
+    }
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "depth {} seldepth {} nodes {} nps {} cutoffs {} ({:.1}% first move) tt hits {} tb hits {} time {}ms",
+            self.depth(), self.seldepth, self.nodes, self.nodes_per_second(), self.beta_cutoffs,
+            self.first_move_cutoff_rate() * 100., self.tt_hits, self.tablebase_hits, self.elapsed.as_millis())
+    }
+}
+
+// Atomics, since the main thread reads the helper threads' counters while
+// they are still searching.
+#[derive(Default)]
+struct Counters {
+    nodes: AtomicU64,
+    beta_cutoffs: AtomicU64,
+    first_move_cutoffs: AtomicU64,
+    tt_hits: AtomicU64,
+    tablebase_hits: AtomicU64,
+    seldepth: AtomicUsize,
+}
+
+pub struct Searcher<'a> {
+    pub evaluator: &'a dyn Evaluator,
+    pub deadline: Option<Instant>,
+    // Set from another thread to stop the search early.
+    pub cancel: Option<&'a AtomicBool>,
+    pub tt: Option<&'a TranspositionTable>,
+    pub tablebase: Option<&'a Tablebase>,
+    stopped: AtomicBool,
+    counters: Counters,
+}
+
+impl<'a> Searcher<'a> {
+    pub fn new(evaluator: &'a dyn Evaluator) -> Self {
+        Self::with_deadline(evaluator, None)
+    }
+
+    pub fn with_deadline(evaluator: &'a dyn Evaluator, deadline: Option<Instant>) -> Self {
+        Self {
+            evaluator,
+            deadline,
+            cancel: None,
+            tt: None,
+            tablebase: None,
+            stopped: AtomicBool::new(false),
+            counters: Counters::default(),
+        }
+    }
+
+    // The counters so far; iterations and elapsed time are left for the
+    // caller, which knows about them.
+    pub fn stats(&self) -> SearchStats {
+        SearchStats {
+            nodes: self.counters.nodes.load(Ordering::Relaxed),
+            beta_cutoffs: self.counters.beta_cutoffs.load(Ordering::Relaxed),
+            first_move_cutoffs: self.counters.first_move_cutoffs.load(Ordering::Relaxed),
+            tt_hits: self.counters.tt_hits.load(Ordering::Relaxed),
+            tablebase_hits: self.counters.tablebase_hits.load(Ordering::Relaxed),
+            seldepth: self.counters.seldepth.load(Ordering::Relaxed),
+            ..SearchStats::default()
+        }
+    }
+
+    // True once the deadline has passed or the search was cancelled. Scores
+    // returned after that point are meaningless and must be thrown away by
+    // the caller.
+    pub fn stopped(&self) -> bool {
+        if self.stopped.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self.cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            self.stopped.store(true, Ordering::Relaxed);
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.stopped.store(true, Ordering::Relaxed);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // Alpha-beta in negamax form: every score is from the point of view of
+    // the side to move, so one branch serves both colors.
+    pub fn negamax(&self, board: &Checkers, depth: usize, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.stopped() {
+            return 0;
+        }
+        self.counters.nodes.fetch_add(1, Ordering::Relaxed);
+        self.counters.seldepth.fetch_max(ply, Ordering::Relaxed);
+        if let Some(value) = self.tablebase.and_then(|tb| tb.probe(board)) {
+            self.counters.tablebase_hits.fetch_add(1, Ordering::Relaxed);
+            return tablebase_score(value, ply);
+        }
+        let hash = board.position_hash();
+        let tt_entry = self.tt.and_then(|tt| tt.probe(hash, ply));
+        if let Some(entry) = tt_entry {
+            self.counters.tt_hits.fetch_add(1, Ordering::Relaxed);
+            if entry.depth as usize >= depth {
+                match entry.bound {
+                    Bound::Exact => return entry.score,
+                    Bound::Lower if entry.score >= beta => return entry.score,
+                    Bound::Upper if entry.score <= alpha => return entry.score,
+                    _ => (),
+                }
+            }
+        }
+        let mut moves: Vec<(usize, (usize, usize, Move))> = board.get_all_moves().into_iter().enumerate().collect();
+        if moves.is_empty() {
+            return -(WIN_SCORE - ply as i32);
+        }
+        if depth == 0 {
+            return side_sign(board) * self.evaluator.evaluate(board);
+        }
+        // the best move from an earlier visit is tried first
+        if let Some(index) = tt_entry.and_then(|entry| entry.best_move) {
+            if (index as usize) < moves.len() {
+                moves.swap(0, index as usize);
+            }
+        }
+        let original_alpha = alpha;
+        let mut best_val = -INFINITY;
+        let mut best_index = None;
+        for (tried, (index, (j, i, m))) in moves.into_iter().enumerate() {
+            let mut copy = *board;
+            copy.make_move_from_enum(j, i, &m).expect("move from enum function failed");
+            let value = -self.negamax(&copy, depth - 1, ply + 1, -beta, -alpha);
+            if value > best_val {
+                best_val = value;
+                best_index = Some(index);
+            }
+            alpha = std::cmp::max(alpha, best_val);
+            if alpha >= beta {
+                self.counters.beta_cutoffs.fetch_add(1, Ordering::Relaxed);
+                if tried == 0 {
+                    self.counters.first_move_cutoffs.fetch_add(1, Ordering::Relaxed);
+                }
+                break;
+            }
+        }
+        if let (Some(tt), false) = (self.tt, self.stopped()) {
+            let bound = if best_val >= beta {
+                Bound::Lower
+            } else if best_val <= original_alpha {
+                Bound::Upper
+            } else {
+                Bound::Exact
+            };
+            tt.store(hash, ply, TtEntry {
+                score: best_val,
+                depth: depth.min(u8::MAX as usize) as u8,
+                bound,
+                best_move: best_index.and_then(|index| u8::try_from(index).ok()),
+            });
+        }
+        best_val
+    }
+
+    // Scores every root move to `depth`. Returns None if the search was
+    // stopped before all of them finished.
+    pub fn score_root_moves(&self, board: &Checkers, depth: usize) -> Option<Vec<ScoredMove>> {
+        self.search_root(board, board.get_all_moves(), depth, true)
+    }
+
+    // Searches the root moves in the given order. With `exact` every move
+    // gets a full window and so a true score; otherwise moves that cannot
+    // beat the best so far only get an upper bound, which is much cheaper.
+    // The window stops one below the best so far, so moves that tie with the
+    // best still get their true score and ties can be broken fairly.
+    fn search_root(&self, board: &Checkers, moves: Vec<(usize, usize, Move)>, depth: usize, exact: bool) -> Option<Vec<ScoredMove>> {
+        let mut alpha = -INFINITY;
+        let mut scored = Vec::with_capacity(moves.len());
+        for (j, i, m) in moves {
+            let mut new_board = *board;
+            new_board.make_move_from_enum(j, i, &m).expect("move from enum failed");
+            let tie_alpha = alpha.max(-INFINITY + 1) - 1;
+            let score = -self.negamax(&new_board, depth.saturating_sub(1), 1, -INFINITY, -tie_alpha);
+            if !exact {
+                alpha = alpha.max(score);
+            }
+            scored.push((score, (j, i, m)));
+        }
+        if self.stopped() {
+            None
+        } else {
+            Some(scored)
+        }
+    }
+}
+
+fn combined_stats(searchers: &[&Searcher]) -> SearchStats {
+    let mut total = SearchStats::default();
+    for stats in searchers.iter().map(|searcher| searcher.stats()) {
+        total.nodes += stats.nodes;
+        total.beta_cutoffs += stats.beta_cutoffs;
+        total.first_move_cutoffs += stats.first_move_cutoffs;
+        total.tt_hits += stats.tt_hits;
+        total.tablebase_hits += stats.tablebase_hits;
+        total.seldepth = total.seldepth.max(stats.seldepth);
+    }
+    total
+}
+
+// Iterative deepening up to `options.max_depth`, then noise and blunders are
+// applied to the deepest completed result.
+pub fn best_move(board: &Checkers, options: &SearchOptions, evaluator: &dyn Evaluator) -> Result<(usize, usize, Move), CheckersError> {
+    best_move_cancellable(board, options, evaluator, &AtomicBool::new(false))
+}
+
+// Like `best_move`, but setting `cancel` stops the search and plays the best
+// move of the deepest finished iteration.
+pub fn best_move_cancellable(board: &Checkers, options: &SearchOptions, evaluator: &dyn Evaluator, cancel: &AtomicBool) -> Result<(usize, usize, Move), CheckersError> {
+    best_move_with_stats(board, options, evaluator, cancel, &mut |_| ()).0
+}
+
+// The full search: `report` is called with the statistics so far after every
+// finished iteration, and the final statistics are returned with the move.
+pub fn best_move_with_stats(
+    board: &Checkers,
+    options: &SearchOptions,
+    evaluator: &dyn Evaluator,
+    cancel: &AtomicBool,
+    report: &mut dyn FnMut(&SearchStats),
+) -> (Result<(usize, usize, Move), CheckersError>, SearchStats) {
+    let options = &effective_options(options);
+    let tt = TranspositionTable::new(DEFAULT_TT_ENTRIES);
+    let exact = options.eval_noise > 0 || options.multi_pv > 1;
+    let (scored, stats) = search_scored(board, options, evaluator, cancel, &tt, exact, report);
+    (pick_move(scored, options, &mut options.rng(board)), stats)
+}
+
+// The best `lines` root moves, best first, each with its true score and
+// principal variation. Noise, blunders and `options.multi_pv` play no part.
+pub fn multi_pv(
+    board: &Checkers,
+    options: &SearchOptions,
+    evaluator: &dyn Evaluator,
+    lines: usize,
+    cancel: &AtomicBool,
+) -> (Vec<PvLine>, SearchStats) {
+    let options = &effective_options(options);
+    let tt = TranspositionTable::new(DEFAULT_TT_ENTRIES);
+    let (mut scored, stats) = search_scored(board, options, evaluator, cancel, &tt, true, &mut |_| ());
+    // stable, so equal scores stay in generation order
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    let length = stats.depth().max(1);
+    let pv = scored.into_iter().take(lines).map(|(score, root)| PvLine {
+        score,
+        moves: principal_variation(board, root, &tt, length),
+    }).collect();
+    (pv, stats)
+}
+
+fn effective_options(options: &SearchOptions) -> SearchOptions {
+    match options.deterministic_seed {
+        Some(_) => SearchOptions { threads: 1, time_budget: None, ..*options },
+        None => *options,
+    }
+}
+
+// Follows the best moves stored in the table from the position after `root`,
+// stopping at `length` moves, a missing entry or a repeated position.
+fn principal_variation(board: &Checkers, root: (usize, usize, Move), tt: &TranspositionTable, length: usize) -> Vec<(usize, usize, Move)> {
+    let mut position = *board;
+    let mut seen = HashSet::new();
+    let mut line = Vec::new();
+    let mut next = Some(root);
+    while let Some((j, i, m)) = next.take() {
+        if position.make_move_from_enum(j, i, &m).is_err() {
+            break;
+        }
+        line.push((j, i, m));
+        if line.len() >= length || !seen.insert(position.position_hash()) {
+            break;
+        }
+        next = tt.probe(position.position_hash(), line.len())
+            .and_then(|entry| entry.best_move)
+            .and_then(|index| position.get_all_moves().into_iter().nth(index as usize));
+    }
+    line
+}
+
+// Iterative deepening with Lazy SMP helpers. Returns the root moves of the
+// deepest finished iteration, scored and in generation order.
+fn search_scored(
+    board: &Checkers,
+    options: &SearchOptions,
+    evaluator: &dyn Evaluator,
+    cancel: &AtomicBool,
+    tt: &TranspositionTable,
+    exact: bool,
+    report: &mut dyn FnMut(&SearchStats),
+) -> (Vec<ScoredMove>, SearchStats) {
+    let start = Instant::now();
+    let deadline = options.time_budget.map(|budget| start + budget);
+    let mut searcher = Searcher::with_deadline(evaluator, deadline);
+    searcher.cancel = Some(cancel);
+    searcher.tt = Some(tt);
+    searcher.tablebase = options.tablebase;
+    let helpers_done = AtomicBool::new(false);
+    let helpers: Vec<Searcher> = (1..options.threads.max(1)).map(|_| {
+        let mut helper = Searcher::with_deadline(evaluator, deadline);
+        helper.cancel = Some(&helpers_done);
+        helper.tt = Some(tt);
+        helper.tablebase = options.tablebase;
+        helper
+    }).collect();
+    let all: Vec<&Searcher> = std::iter::once(&searcher).chain(helpers.iter()).collect();
+    let root_moves = board.get_all_moves();
+
+    let mut scored: Vec<ScoredMove> = Vec::new();
+    let mut iterations = Vec::new();
+    thread::scope(|scope| {
+        // Lazy SMP: helpers run their own iterative deepening on the same
+        // table, half of them a ply deeper and each with the root moves in a
+        // different order, and the main thread picks up whatever they store.
+        for (id, helper) in helpers.iter().enumerate() {
+            let mut moves = root_moves.clone();
+            if !moves.is_empty() {
+                let shift = (id + 1) % moves.len();
+                moves.rotate_left(shift);
+            }
+            scope.spawn(move || {
+                for depth in 1..=options.max_depth.max(1) {
+                    if helper.search_root(board, moves.clone(), depth + id % 2, exact).is_none() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let mut nodes_before = 0;
+        let mut moves = root_moves.clone();
+        for depth in 1..=options.max_depth.max(1) {
+            let iteration_start = Instant::now();
+            match searcher.search_root(board, moves.clone(), depth, exact) {
+                Some(result) => scored = result,
+                None => break,
+            }
+            let stats = combined_stats(&all);
+            // ties go to the first move in generation order, as in pick_move,
+            // so the reported line starts with the move that gets played
+            let best = scored.iter().min_by_key(|(score, mv)| (std::cmp::Reverse(*score), root_moves.iter().position(|m| m == mv)));
+            if let Some((score, best_move)) = best {
+                iterations.push(IterationStats {
+                    depth,
+                    score: *score,
+                    best_move: best_move.clone(),
+                    nodes: stats.nodes - nodes_before,
+                    elapsed: iteration_start.elapsed(),
+                });
+                // the next iteration starts with this one's best move
+                if let Some(position) = moves.iter().position(|m| m == best_move) {
+                    moves[..=position].rotate_right(1);
+                }
+            }
+            nodes_before = stats.nodes;
+            report(&SearchStats { elapsed: start.elapsed(), iterations: iterations.clone(), ..stats });
+        }
+        helpers_done.store(true, Ordering::Relaxed);
+    });
+    if scored.is_empty() {
+        // not even depth one finished in time, so fall back to a static look
+        let mut fallback = Searcher::new(evaluator);
+        fallback.tablebase = options.tablebase;
+        scored = fallback.score_root_moves(board, 1).unwrap_or_default();
+    }
+    // back into generation order, so ties go to the same move whatever order
+    // the search happened to try them in
+    scored.sort_by_key(|(_, mv)| root_moves.iter().position(|m| m == mv));
+    let stats = SearchStats { elapsed: start.elapsed(), iterations, ..combined_stats(&all) };
+    (scored, stats)
+}
+
+fn pick_move(mut scored: Vec<ScoredMove>, options: &SearchOptions, rng: &mut StdRng) -> Result<(usize, usize, Move), CheckersError> {
+    if options.eval_noise > 0 {
+        for (score, _) in scored.iter_mut() {
+            *score = score.saturating_add(rng.gen_range(-options.eval_noise..=options.eval_noise));
+        }
+    }
+    let mut best: Option<usize> = None;
+    for (index, (score, _)) in scored.iter().enumerate() {
+        match best {
+            Some(b) if scored[b].0 >= *score => (),
+            _ => best = Some(index),
+        }
+    }
+    let mut best = best.ok_or(CheckersError::GameOver)?;
+    if options.multi_pv > 1 {
+        let best_score = scored[best].0;
+        let mut candidates: Vec<usize> = (0..scored.len()).collect();
+        candidates.sort_by_key(|&index| std::cmp::Reverse(scored[index].0));
+        candidates.truncate(options.multi_pv);
+        candidates.retain(|&index| scored[index].0 >= best_score.saturating_sub(options.pv_margin));
+        best = *candidates.choose(rng).expect("the best move is a candidate");
+    }
+    if scored.len() > 1 && rng.gen_bool(options.blunder_chance.clamp(0., 1.)) {
+        let others: Vec<usize> = (0..scored.len()).filter(|&index| index != best).collect();
+        let blunder = *others.choose(rng).expect("more than one move");
+        return Ok(scored.swap_remove(blunder).1);
+    }
+    Ok(scored.swap_remove(best).1)
+}
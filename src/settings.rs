@@ -0,0 +1,86 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::difficulty::Difficulty;
+use crate::evaluator::EvaluatorKind;
+use crate::mode::GameMode;
+use crate::search::{default_threads, SearchOptions};
+
+pub const SETTINGS_FILE: &str = "checkers_settings.txt";
+
+// Preferences kept between sessions, stored as `key=value` lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    // Keep searching on the human's time.
+    pub ponder: bool,
+    // Search threads; defaults to one per core.
+    pub threads: usize,
+    // Plays reproducible games when set; see `SearchOptions::deterministic`.
+    pub seed: Option<u64>,
+    pub evaluator: EvaluatorKind,
+    pub mode: GameMode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::default(),
+            ponder: true,
+            threads: default_threads(),
+            seed: None,
+            evaluator: EvaluatorKind::default(),
+            mode: GameMode::default(),
+        }
+    }
+}
+
+impl Settings {
+    // Missing files and unknown or malformed lines fall back to defaults.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let mut settings = Settings::default();
+        if let Ok(contents) = fs::read_to_string(path) {
+            for line in contents.lines() {
+                if let Some((key, value)) = line.split_once('=') {
+                    match key.trim() {
+                        "difficulty" => if let Ok(difficulty) = value.parse() {
+                            settings.difficulty = difficulty;
+                        },
+                        "ponder" => if let Ok(ponder) = value.trim().parse() {
+                            settings.ponder = ponder;
+                        },
+                        "threads" => if let Ok(threads) = value.trim().parse::<usize>() {
+                            settings.threads = threads.max(1);
+                        },
+                        "seed" => settings.seed = value.trim().parse().ok(),
+                        "evaluator" => if let Ok(evaluator) = value.parse() {
+                            settings.evaluator = evaluator;
+                        },
+                        "mode" => if let Ok(mode) = value.parse() {
+                            settings.mode = mode;
+                        },
+                        _ => (),
+                    }
+                }
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut contents = format!("difficulty={}\nponder={}\nthreads={}\nevaluator={}\nmode={}\n", self.difficulty, self.ponder, self.threads, self.evaluator, self.mode);
+        if let Some(seed) = self.seed {
+            contents.push_str(&format!("seed={}\n", seed));
+        }
+        fs::write(path, contents)
+    }
+
+    // The difficulty's search settings on the configured number of threads.
+    pub fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            deterministic_seed: self.seed,
+            ..self.difficulty.search_options(self.threads)
+        }
+    }
+}
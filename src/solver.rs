@@ -0,0 +1,254 @@
+use std::fmt;
+
+use crate::checkers::{Checkers, Move};
+use crate::tablebase::{Tablebase, TbValue};
+
+// Proof and disproof numbers of a solved node.
+const INFINITE: u32 = u32::MAX;
+
+// Game theoretic value for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Win,
+    Loss,
+    // Neither side can force a win within `max_plies`. A position repeating
+    // one earlier in the same line counts as a draw.
+    Draw,
+    // The node budget ran out first.
+    Unknown,
+}
+
+impl Verdict {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verdict::Win => "win",
+            Verdict::Loss => "loss",
+            Verdict::Draw => "draw",
+            Verdict::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolverOptions {
+    // Nodes the two proof trees may hold between them. Each node keeps its
+    // board, so this is also the memory limit.
+    pub node_budget: usize,
+    // Lines longer than this are cut off and count as draws.
+    pub max_plies: usize,
+    // Settles positions it knows without searching them.
+    pub tablebase: Option<&'static Tablebase>,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            node_budget: 200_000,
+            max_plies: 120,
+            tablebase: None,
+        }
+    }
+}
+
+// The verdict and, when it is not unknown, a line of best play: for a win
+// the winner's moves with the loser's longest defence, for a draw moves that
+// keep either side from winning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub verdict: Verdict,
+    pub line: Vec<(usize, usize, Move)>,
+    pub nodes: usize,
+}
+
+struct Node {
+    board: Checkers,
+    hash: u64,
+    from_parent: Option<(usize, usize, Move)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    ply: usize,
+    expanded: bool,
+    proof: u32,
+    disproof: u32,
+}
+
+// A proof-number search tree trying to prove that `attacker` (the sign of
+// `current_turn`) wins. Nodes where the attacker is to move are OR nodes,
+// the others AND nodes.
+struct ProofTree {
+    attacker: i32,
+    max_plies: usize,
+    tablebase: Option<&'static Tablebase>,
+    nodes: Vec<Node>,
+}
+
+impl ProofTree {
+    fn new(board: &Checkers, attacker: i32, options: &SolverOptions) -> Self {
+        let mut tree = Self { attacker, max_plies: options.max_plies, tablebase: options.tablebase, nodes: Vec::new() };
+        tree.add_node(*board, None, None);
+        tree
+    }
+
+    fn is_or(&self, node: usize) -> bool {
+        self.nodes[node].board.current_turn.signum() == self.attacker
+    }
+
+    fn solved(&self) -> bool {
+        self.nodes[0].proof == 0 || self.nodes[0].disproof == 0
+    }
+
+    // Adds a node with proof and disproof numbers from what can be told
+    // without searching: the game is over, the tablebase knows it, the line
+    // repeats or is too long, or else the number of moves to choose from.
+    fn add_node(&mut self, board: Checkers, from_parent: Option<(usize, usize, Move)>, parent: Option<usize>) -> usize {
+        let hash = board.position_hash();
+        let ply = parent.map_or(0, |p| self.nodes[p].ply + 1);
+        let attacker_to_move = board.current_turn.signum() == self.attacker;
+        let moves = board.get_all_moves().len();
+        let mut ancestor = parent;
+        let mut repeated = false;
+        while let Some(a) = ancestor {
+            if self.nodes[a].hash == hash {
+                repeated = true;
+                break;
+            }
+            ancestor = self.nodes[a].parent;
+        }
+        // whether the attacker has won, when it is already decided
+        let attacker_wins = if moves == 0 {
+            Some(!attacker_to_move)
+        } else if repeated || ply >= self.max_plies {
+            Some(false)
+        } else {
+            match self.tablebase.and_then(|tb| tb.probe(&board)) {
+                Some(TbValue::Win(_)) => Some(attacker_to_move),
+                Some(TbValue::Loss(_)) => Some(!attacker_to_move),
+                Some(TbValue::Draw) => Some(false),
+                None => None,
+            }
+        };
+        let (proof, disproof, expanded) = match attacker_wins {
+            Some(true) => (0, INFINITE, true),
+            Some(false) => (INFINITE, 0, true),
+            None if attacker_to_move => (1, moves as u32, false),
+            None => (moves as u32, 1, false),
+        };
+        self.nodes.push(Node { board, hash, from_parent, parent, children: Vec::new(), ply, expanded, proof, disproof });
+        self.nodes.len() - 1
+    }
+
+    // Follows the children that decide the root's numbers down to a leaf.
+    fn most_proving(&self) -> usize {
+        let mut node = 0;
+        while self.nodes[node].expanded {
+            let children = &self.nodes[node].children;
+            node = if self.is_or(node) {
+                *children.iter().min_by_key(|&&c| self.nodes[c].proof).expect("expanded node has children")
+            } else {
+                *children.iter().min_by_key(|&&c| self.nodes[c].disproof).expect("expanded node has children")
+            };
+        }
+        node
+    }
+
+    fn expand(&mut self, node: usize) {
+        let board = self.nodes[node].board;
+        for (j, i, m) in board.get_all_moves() {
+            let mut child = board;
+            child.make_move_from_enum(j, i, &m).expect("move from enum failed");
+            let index = self.add_node(child, Some((j, i, m)), Some(node));
+            self.nodes[node].children.push(index);
+        }
+        self.nodes[node].expanded = true;
+    }
+
+    // Recomputes the numbers from `node` up, stopping once they no longer
+    // change.
+    fn update_ancestors(&mut self, mut node: usize) {
+        loop {
+            let children = &self.nodes[node].children;
+            let proofs = children.iter().map(|&c| self.nodes[c].proof);
+            let disproofs = children.iter().map(|&c| self.nodes[c].disproof);
+            let (proof, disproof) = if self.is_or(node) {
+                (proofs.min().unwrap_or(INFINITE), disproofs.fold(0, u32::saturating_add))
+            } else {
+                (proofs.fold(0, u32::saturating_add), disproofs.min().unwrap_or(INFINITE))
+            };
+            if proof == self.nodes[node].proof && disproof == self.nodes[node].disproof {
+                return;
+            }
+            self.nodes[node].proof = proof;
+            self.nodes[node].disproof = disproof;
+            match self.nodes[node].parent {
+                Some(parent) => node = parent,
+                None => return,
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        let node = self.most_proving();
+        self.expand(node);
+        self.update_ancestors(node);
+    }
+
+    // The line through a solved tree. The side that has the result in hand
+    // plays a child that keeps it; the other side plays the child whose
+    // subtree took the most work to settle, a stand-in for the most stubborn
+    // defence.
+    fn line(&self) -> Vec<(usize, usize, Move)> {
+        let proved = self.nodes[0].proof == 0;
+        let mut line = Vec::new();
+        let mut node = 0;
+        while !self.nodes[node].children.is_empty() {
+            let children = &self.nodes[node].children;
+            let keeps = |c: &usize| if proved { self.nodes[*c].proof == 0 } else { self.nodes[*c].disproof == 0 };
+            let next = if self.is_or(node) == proved {
+                children.iter().find(|c| keeps(c))
+            } else {
+                children.iter().max_by_key(|&&c| self.subtree_size(c))
+            };
+            let Some(&next) = next else { break };
+            line.push(self.nodes[next].from_parent.clone().expect("child has a move"));
+            node = next;
+        }
+        line
+    }
+
+    fn subtree_size(&self, node: usize) -> usize {
+        1 + self.nodes[node].children.iter().map(|&c| self.subtree_size(c)).sum::<usize>()
+    }
+}
+
+// Proves the value of `board` for the side to move with two proof-number
+// searches grown side by side: one trying to prove a win for the side to
+// move, one a win for the opponent. A draw is both disproved.
+pub fn solve(board: &Checkers, options: &SolverOptions) -> Solution {
+    let side = board.current_turn.signum();
+    let mut ours = ProofTree::new(board, side, options);
+    let mut theirs = ProofTree::new(board, -side, options);
+    loop {
+        let nodes = ours.nodes.len() + theirs.nodes.len();
+        let (verdict, line) = if ours.nodes[0].proof == 0 {
+            (Verdict::Win, ours.line())
+        } else if theirs.nodes[0].proof == 0 {
+            (Verdict::Loss, theirs.line())
+        } else if ours.solved() && theirs.solved() {
+            (Verdict::Draw, theirs.line())
+        } else if nodes >= options.node_budget {
+            (Verdict::Unknown, Vec::new())
+        } else {
+            // grow the smaller of the unsolved trees
+            let grow_ours = !ours.solved() && (theirs.solved() || ours.nodes.len() <= theirs.nodes.len());
+            if grow_ours { ours.step() } else { theirs.step() }
+            continue;
+        };
+        return Solution { verdict, line, nodes };
+    }
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::checkers::Checkers;
+
+pub const TABLEBASE_FILE: &str = "endgame.tb";
+const MAGIC: &[u8; 4] = b"CKTB";
+const VERSION: u8 = 2;
+
+// A byte per position is 430 MB at five pieces, and generating it peaks at
+// 1.4 GB; six pieces would need 7.4 GB for the values alone.
+pub const MAX_TABLEBASE_PIECES: usize = 5;
+
+const SQUARES: usize = 32;
+
+// Marks a position with a capture that does not lose, so it can never be lost.
+const CANNOT_LOSE: u8 = u8::MAX;
+
+// Game theoretic value for the side to move, with the distance in plies to
+// the end of the game under best play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TbValue {
+    Win(u16),
+    Loss(u16),
+    Draw,
+}
+
+// One byte per position with both colors on the board, in `rank` order and
+// split by piece count. 0 is a draw, anything else the distance in plies
+// plus one; wins are always an odd number of plies away and losses an even
+// number, so the parity tells them apart.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Tablebase {
+    pub max_pieces: usize,
+    // slices[n] holds the positions with n pieces.
+    slices: Vec<Vec<u8>>,
+}
+
+// binomial(n, k) for every square count and piece count.
+const BINOMIAL: [[usize; MAX_TABLEBASE_PIECES + 1]; SQUARES + 1] = {
+    let mut table = [[0; MAX_TABLEBASE_PIECES + 1]; SQUARES + 1];
+    let mut n = 0;
+    while n <= SQUARES {
+        table[n][0] = 1;
+        let mut k = 1;
+        while k <= MAX_TABLEBASE_PIECES && n > 0 {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+};
+
+const PIECES: [i32; 4] = [1, 3, -1, -3];
+
+fn square_coords(index: usize) -> (usize, usize) {
+    let j = index / 4;
+    (j, 2 * (index % 4) + (j + 1) % 2)
+}
+
+fn kind_bits(piece: i32) -> usize {
+    PIECES.iter().position(|&p| p == piece).expect("a piece")
+}
+
+// Positions with `count` pieces: both sides to move, every set of occupied
+// squares and every kind on each of them. Some are impossible, such as a man
+// on its last row, and stay draws that are never probed.
+fn slice_len(count: usize) -> usize {
+    (2 * BINOMIAL[SQUARES][count]) << (2 * count)
+}
+
+// The piece count and the position's index among those with that many:
+// side to move, then the occupied squares in combinatorial order, then two
+// bits per piece for its kind. None past `MAX_TABLEBASE_PIECES`.
+fn rank(board: &Checkers) -> Option<(usize, usize)> {
+    let mut count = 0;
+    let mut squares = 0;
+    let mut kinds = 0;
+    for (index, binomial) in BINOMIAL.iter().enumerate().take(SQUARES) {
+        let (j, i) = square_coords(index);
+        let piece = board.board_state[j][i];
+        if piece != 0 {
+            if count == MAX_TABLEBASE_PIECES {
+                return None;
+            }
+            kinds |= kind_bits(piece) << (2 * count);
+            count += 1;
+            squares += binomial[count];
+        }
+    }
+    let side = usize::from(board.current_turn > 0);
+    Some((count, (side * BINOMIAL[SQUARES][count] + squares) << (2 * count) | kinds))
+}
+
+// The position at `index` among those with `count` pieces, or None if it
+// cannot come up in a game or has only one color on the board.
+fn unrank(count: usize, index: usize) -> Option<Checkers> {
+    let kinds = index & ((1 << (2 * count)) - 1);
+    let rest = index >> (2 * count);
+    let side = rest / BINOMIAL[SQUARES][count];
+    let mut squares = rest % BINOMIAL[SQUARES][count];
+    let mut board_state = [[0; 8]; 8];
+    let mut square = SQUARES;
+    for n in (0..count).rev() {
+        square -= 1;
+        while BINOMIAL[square][n + 1] > squares {
+            square -= 1;
+        }
+        squares -= BINOMIAL[square][n + 1];
+        let piece = PIECES[(kinds >> (2 * n)) & 3];
+        let (j, i) = square_coords(square);
+        // men are promoted on arrival, so none sit on their last row
+        if piece == 1 && j == 0 || piece == -1 && j == 7 {
+            return None;
+        }
+        board_state[j][i] = piece;
+    }
+    let pieces = board_state.iter().flatten();
+    if !pieces.clone().any(|&p| p > 0) || !pieces.clone().any(|&p| p < 0) {
+        return None;
+    }
+    Some(Checkers::from_position(board_state, if side == 1 { 1 } else { -1 }))
+}
+
+fn encode(value: TbValue) -> u8 {
+    match value {
+        TbValue::Win(plies) | TbValue::Loss(plies) => u8::try_from(plies + 1).ok().filter(|&byte| byte != CANNOT_LOSE)
+            .expect("no game this small lasts that long"),
+        TbValue::Draw => 0,
+    }
+}
+
+fn decode(byte: u8) -> TbValue {
+    match byte {
+        0 => TbValue::Draw,
+        _ if byte.is_multiple_of(2) => TbValue::Win(byte as u16 - 1),
+        _ => TbValue::Loss(byte as u16 - 1),
+    }
+}
+
+// The values are hundreds of megabytes, so only the size is shown.
+impl fmt::Debug for Tablebase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tablebase").field("max_pieces", &self.max_pieces).finish_non_exhaustive()
+    }
+}
+
+impl Tablebase {
+    // None when the position is outside the table, including positions where
+    // one side has already been wiped out.
+    pub fn probe(&self, board: &Checkers) -> Option<TbValue> {
+        if board.required_square.is_some() {
+            return None;
+        }
+        self.lookup(board)
+    }
+
+    fn lookup(&self, board: &Checkers) -> Option<TbValue> {
+        let pieces = board.board_state.iter().flatten();
+        if !pieces.clone().any(|&p| p > 0) || !pieces.clone().any(|&p| p < 0) {
+            return None;
+        }
+        let (count, index) = rank(board)?;
+        self.slices.get(count)?.get(index).map(|&byte| decode(byte))
+    }
+
+    // Decisive positions in the table.
+    pub fn len(&self) -> usize {
+        self.slices.iter().flatten().filter(|&&byte| byte != 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Layout: magic, version, max pieces, then the bytes of every slice from
+    // two pieces up.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(6 + self.slices.iter().map(Vec::len).sum::<usize>());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.max_pieces as u8);
+        for slice in &self.slices {
+            bytes.extend_from_slice(slice);
+        }
+        fs::write(path, bytes)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        if bytes.len() < 6 || &bytes[0..4] != MAGIC {
+            return Err(invalid("not a tablebase file"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid("unsupported tablebase version"));
+        }
+        let max_pieces = bytes[5] as usize;
+        if max_pieces > MAX_TABLEBASE_PIECES {
+            return Err(invalid("too many pieces"));
+        }
+        let mut rest = &bytes[6..];
+        let mut slices = vec![Vec::new(); 2];
+        for count in 2..=max_pieces {
+            if rest.len() < slice_len(count) {
+                return Err(invalid("truncated tablebase file"));
+            }
+            let (slice, after) = rest.split_at(slice_len(count));
+            slices.push(slice.to_vec());
+            rest = after;
+        }
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes in tablebase file"));
+        }
+        Ok(Self { max_pieces, slices })
+    }
+
+    // Loads a tablebase that stays for the rest of the program, for the
+    // search and solver options to refer to.
+    pub fn load_static(path: impl AsRef<Path>) -> io::Result<&'static Self> {
+        Self::load(path).map(|tablebase| &*Box::leak(Box::new(tablebase)))
+    }
+}
+
+// The positions that reach `board` with a plain move by the side not to
+// move there: a piece of theirs stepped back, and a king on their last row
+// possibly a man before the move. Captures are taken back nowhere, since
+// they lead to smaller tables, and the step must have been legal, so not
+// possible while a capture was on.
+fn unmoves(board: &Checkers) -> Vec<Checkers> {
+    let mover = -board.current_turn.signum();
+    let mut parents = Vec::new();
+    for (j, row) in board.board_state.iter().enumerate() {
+        for (i, &piece) in row.iter().enumerate() {
+            if piece * mover <= 0 {
+                continue;
+            }
+            // men step forward, black towards row 0 and red towards row 7
+            let behind = if mover > 0 { j + 1 } else { j.wrapping_sub(1) };
+            let last_row = if mover > 0 { 0 } else { 7 };
+            for (from_j, from_i) in [(j + 1, i + 1), (j + 1, i.wrapping_sub(1)), (j.wrapping_sub(1), i + 1), (j.wrapping_sub(1), i.wrapping_sub(1))] {
+                if from_j > 7 || from_i > 7 || board.board_state[from_j][from_i] != 0 {
+                    continue;
+                }
+                let mut before = Vec::new();
+                if piece.abs() == 3 {
+                    before.push(piece);
+                }
+                if from_j == behind && (piece.abs() == 1 || j == last_row) {
+                    before.push(mover);
+                }
+                for earlier in before {
+                    let mut parent = *board;
+                    parent.current_turn = mover;
+                    parent.board_state[j][i] = 0;
+                    parent.board_state[from_j][from_i] = earlier;
+                    let legal = parent.get_all_moves().into_iter().any(|(mj, mi, m)| {
+                        let mut after = parent;
+                        (mj, mi) == (from_j, from_i)
+                            && after.make_move_from_enum(mj, mi, &m).is_ok()
+                            && after.board_state == board.board_state
+                    });
+                    if legal {
+                        parents.push(parent);
+                    }
+                }
+            }
+        }
+    }
+    parents
+}
+
+// Solves the positions with `count` pieces, given every smaller table. Each
+// position's moves are generated once: captures lead into the smaller
+// tables, which settle the position or bound it, and plain moves are only
+// counted. Positions are then settled in order of distance, starting from
+// those without moves; each settled position passes its value on to the
+// positions reaching it with a plain move, found by taking that move back.
+// Those reaching a lost position are won, and those whose every move reaches
+// a won position are lost. Whatever is left unsettled is a draw.
+fn solve_slice(smaller: &Tablebase, count: usize) -> Vec<u8> {
+    let len = slice_len(count);
+    let mut values = vec![0u8; len];
+    // Plain moves to positions not yet known to be won for the opponent.
+    let mut open = vec![0u8; len];
+    // The longest win among the replies to captures, or CANNOT_LOSE.
+    let mut longest = vec![0u8; len];
+    let mut buckets: Vec<Vec<u32>> = Vec::new();
+    fn settle(values: &mut [u8], buckets: &mut Vec<Vec<u32>>, index: usize, value: TbValue) {
+        let plies = match value {
+            TbValue::Win(plies) | TbValue::Loss(plies) => plies as usize,
+            TbValue::Draw => unreachable!("draws are never settled"),
+        };
+        values[index] = encode(value);
+        if buckets.len() <= plies {
+            buckets.resize(plies + 1, Vec::new());
+        }
+        buckets[plies].push(index as u32);
+    }
+
+    for index in 0..len {
+        let Some(board) = unrank(count, index) else {
+            continue;
+        };
+        let moves = board.get_all_moves();
+        if moves.is_empty() {
+            settle(&mut values, &mut buckets, index, TbValue::Loss(0));
+            continue;
+        }
+        let mut shortest_win: Option<u16> = None;
+        for (j, i, m) in moves {
+            let mut child = board;
+            child.make_move_from_enum(j, i, &m).expect("move from enum failed");
+            if child.piece_count() == count {
+                open[index] += 1;
+                continue;
+            }
+            // a side with nothing left has lost
+            match smaller.lookup(&child).unwrap_or(TbValue::Loss(0)) {
+                TbValue::Loss(plies) => shortest_win = Some(shortest_win.map_or(plies + 1, |w| w.min(plies + 1))),
+                TbValue::Win(plies) if longest[index] != CANNOT_LOSE => longest[index] = longest[index].max(plies as u8),
+                _ => longest[index] = CANNOT_LOSE,
+            }
+        }
+        if let Some(plies) = shortest_win {
+            settle(&mut values, &mut buckets, index, TbValue::Win(plies));
+        } else if open[index] == 0 && longest[index] != CANNOT_LOSE {
+            settle(&mut values, &mut buckets, index, TbValue::Loss(longest[index] as u16 + 1));
+        }
+    }
+
+    let mut plies = 0;
+    while plies < buckets.len() {
+        for index in std::mem::take(&mut buckets[plies]) {
+            let index = index as usize;
+            // settled again since, closer
+            if values[index] != plies as u8 + 1 {
+                continue;
+            }
+            let board = unrank(count, index).expect("settled positions are real");
+            for parent in unmoves(&board) {
+                let (_, parent_index) = rank(&parent).expect("same piece count");
+                let settled = values[parent_index];
+                if plies % 2 == 0 {
+                    // a move to a lost position wins, and this is the
+                    // closest one unless the parent is settled already
+                    if settled == 0 || settled > plies as u8 + 2 {
+                        settle(&mut values, &mut buckets, parent_index, TbValue::Win(plies as u16 + 1));
+                    }
+                } else if settled == 0 {
+                    open[parent_index] -= 1;
+                    if open[parent_index] == 0 && longest[parent_index] != CANNOT_LOSE {
+                        let longest_win = (plies as u16).max(longest[parent_index] as u16);
+                        settle(&mut values, &mut buckets, parent_index, TbValue::Loss(longest_win + 1));
+                    }
+                }
+            }
+        }
+        plies += 1;
+    }
+    values
+}
+
+// Solves every position with up to `max_pieces` pieces (both colors present)
+// by retrograde analysis, smallest tables first. 4 pieces takes about twenty
+// seconds and 5 about twelve minutes.
+pub fn generate(max_pieces: usize) -> Tablebase {
+    let max_pieces = max_pieces.min(MAX_TABLEBASE_PIECES);
+    let mut tablebase = Tablebase { max_pieces, slices: vec![Vec::new(); 2] };
+    for count in 2..=max_pieces {
+        let slice = solve_slice(&tablebase, count);
+        tablebase.slices.push(slice);
+    }
+    tablebase
+}
@@ -0,0 +1,69 @@
+use crate::checkers::Checkers;
+use crate::difficulty::Difficulty;
+use crate::pdn::square_number;
+
+pub const HELP: &str = "\
+moves are typed in notation: 11-15 for a step, 15x22 or 15x22x29 for captures
+  undo          take back your last move
+  hint          suggest a move
+  moves         list the legal moves
+  level <name>  change the difficulty (beginner, easy, medium, hard, expert)
+  fen           print the position
+  help          show this text
+  quit          leave the game";
+
+// The board as text, black at the bottom like in the window. Pieces are
+// b and r, kings B and R; empty playable squares show their number.
+pub fn render_board(board: &Checkers) -> String {
+    let mut text = String::new();
+    for j in 0..8usize {
+        for i in 0..8usize {
+            let cell = if (j + i).is_multiple_of(2) {
+                "  .".to_string()
+            } else {
+                match board.board_state[j][i] {
+                    1 => "  b".to_string(),
+                    3 => "  B".to_string(),
+                    -1 => "  r".to_string(),
+                    -3 => "  R".to_string(),
+                    _ => format!("{:>3}", square_number(j, i)),
+                }
+            };
+            text.push_str(&cell);
+        }
+        text.push('\n');
+    }
+    text.push_str(if board.current_turn > 0 { "black to move" } else { "red to move" });
+    text
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    // A move in notation, still to be checked against the position.
+    Move(String),
+    Undo,
+    Hint,
+    Moves,
+    Level(Difficulty),
+    Fen,
+    Help,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let first = words.next().ok_or_else(|| "type a move or help".to_string())?;
+        match first.to_lowercase().as_str() {
+            "undo" | "u" => Ok(Command::Undo),
+            "hint" | "h" => Ok(Command::Hint),
+            "moves" | "m" => Ok(Command::Moves),
+            "level" => Ok(Command::Level(words.next().unwrap_or("").parse()?)),
+            "fen" => Ok(Command::Fen),
+            "help" | "?" => Ok(Command::Help),
+            "quit" | "q" | "exit" => Ok(Command::Quit),
+            _ if first.starts_with(|c: char| c.is_ascii_digit()) => Ok(Command::Move(first.to_string())),
+            other => Err(format!("unknown command {}, type help for the list", other)),
+        }
+    }
+}
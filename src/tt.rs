@@ -0,0 +1,111 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::search::WIN_SCORE;
+
+// 2^20 entries of 16 bytes, allocated per search.
+pub const DEFAULT_TT_ENTRIES: usize = 1 << 20;
+
+// Scores this close to WIN_SCORE are mates and depend on the ply.
+const MATE_THRESHOLD: i32 = WIN_SCORE - 10_000;
+
+// What the stored score says about the true score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    // The search failed high, so the true score is at least this.
+    Lower,
+    // The search failed low, so the true score is at most this.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    // Index of the best move in `get_all_moves` order.
+    pub best_move: Option<u8>,
+}
+
+impl TtEntry {
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let best_move = self.best_move.map_or(0, |index| index as u64 + 1);
+        self.score as u32 as u64 | (self.depth as u64) << 32 | bound << 40 | best_move << 48
+    }
+
+    fn unpack(data: u64) -> Self {
+        let bound = match (data >> 40) & 3 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        let best_move = ((data >> 48) & 0xffff) as u16;
+        Self {
+            score: data as u32 as i32,
+            depth: (data >> 32) as u8,
+            bound,
+            best_move: best_move.checked_sub(1).map(|index| index as u8),
+        }
+    }
+}
+
+// Shared between search threads without locks. Each slot keeps the key
+// xor'd with the data next to the data, so a slot torn by two threads writing
+// at once fails the key check instead of handing back a mix of both.
+pub struct TranspositionTable {
+    slots: Vec<[AtomicU64; 2]>,
+}
+
+impl TranspositionTable {
+    // `entries` is rounded up to a power of two.
+    pub fn new(entries: usize) -> Self {
+        let slots = (0..entries.max(1).next_power_of_two())
+            .map(|_| [AtomicU64::new(0), AtomicU64::new(0)])
+            .collect();
+        Self { slots }
+    }
+
+    fn slot(&self, hash: u64) -> &[AtomicU64; 2] {
+        &self.slots[hash as usize & (self.slots.len() - 1)]
+    }
+
+    // Mate scores are stored relative to the position rather than the root
+    // and converted back for the ply they are probed at.
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<TtEntry> {
+        let [check, data] = self.slot(hash);
+        let data = data.load(Ordering::Relaxed);
+        if check.load(Ordering::Relaxed) ^ data != hash || data == 0 {
+            return None;
+        }
+        let mut entry = TtEntry::unpack(data);
+        if entry.score > MATE_THRESHOLD {
+            entry.score -= ply as i32;
+        } else if entry.score < -MATE_THRESHOLD {
+            entry.score += ply as i32;
+        }
+        Some(entry)
+    }
+
+    // Keeps a deeper result for the same position over a shallower one.
+    pub fn store(&self, hash: u64, ply: usize, mut entry: TtEntry) {
+        if let Some(existing) = self.probe(hash, ply) {
+            if existing.depth > entry.depth {
+                return;
+            }
+        }
+        if entry.score > MATE_THRESHOLD {
+            entry.score += ply as i32;
+        } else if entry.score < -MATE_THRESHOLD {
+            entry.score -= ply as i32;
+        }
+        let data = entry.pack();
+        let [check, slot_data] = self.slot(hash);
+        check.store(hash ^ data, Ordering::Relaxed);
+        slot_data.store(data, Ordering::Relaxed);
+    }
+}
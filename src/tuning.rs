@@ -0,0 +1,158 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::checkers::Checkers;
+use crate::evaluator::{features, MaterialEvaluator, Weights, FEATURE_COUNT};
+use crate::game::{play_game_from, Game, GameResult};
+use crate::pdn::{from_fen, to_fen};
+use crate::player::{AlphaBetaPlayer, Player, RandomPlayer};
+use crate::search::SearchOptions;
+
+// A position from a finished game with how that game ended for black:
+// 1 for a win, 0.5 for a draw and 0 for a loss.
+#[derive(Clone, Copy)]
+pub struct TrainingPosition {
+    pub board: Checkers,
+    pub outcome: f64,
+}
+
+// Random plies played before the engines take over, so games differ.
+const OPENING_PLIES: usize = 4;
+const MAX_GAME_PLIES: usize = 200;
+
+// Plays `games` engine games in parallel, each from its own short random
+// opening. A little noise and the odd blunder keep the games from repeating
+// each other. Games that fail part way are left out.
+pub fn self_play_games(games: usize, depth: usize, seed: u64) -> Vec<(GameResult, Game)> {
+    let options = SearchOptions {
+        max_depth: depth,
+        time_budget: None,
+        eval_noise: 2,
+        blunder_chance: 0.03,
+        multi_pv: 1,
+        pv_margin: 0,
+        threads: 1,
+        deterministic_seed: None,
+        tablebase: None,
+        book: None,
+    };
+    (0..games).into_par_iter().filter_map(|n| {
+        let mut game = Game::new();
+        let mut opener = RandomPlayer::with_seed(seed.wrapping_add(n as u64));
+        for _ in 0..OPENING_PLIES {
+            match opener.choose_move(&game) {
+                Ok((j, i, m)) => game.play(j, i, &m).expect("random move was legal"),
+                Err(_) => break,
+            }
+        }
+        let options = options.deterministic(seed.wrapping_add(n as u64));
+        let mut black = AlphaBetaPlayer::with_options(options, Box::new(MaterialEvaluator));
+        let mut red = AlphaBetaPlayer::with_options(options, Box::new(MaterialEvaluator));
+        play_game_from(game, &mut black, &mut red, MAX_GAME_PLIES).ok()
+    }).collect()
+}
+
+// Keeps every quiet position (no jump available to the side to move) of
+// `self_play_games` after the random opening.
+pub fn self_play_positions(games: usize, depth: usize, seed: u64) -> Vec<TrainingPosition> {
+    self_play_games(games, depth, seed).into_par_iter().flat_map(|(result, game)| {
+        let outcome = match result {
+            GameResult::BlackWon => 1.,
+            GameResult::RedWon => 0.,
+            GameResult::Draw => 0.5,
+        };
+        let mut board = game.start;
+        let mut positions = Vec::new();
+        for (ply, (j, i, m)) in game.history.iter().enumerate() {
+            let quiet = !board.get_all_moves().iter().any(|(_, _, mv)| mv.is_jump());
+            if ply >= OPENING_PLIES && quiet {
+                positions.push(TrainingPosition { board, outcome });
+            }
+            board.make_move_from_enum(*j, *i, m).expect("replayed move failed");
+        }
+        positions
+    }).collect()
+}
+
+// One position per line: FEN, then the outcome.
+pub fn save_positions(path: impl AsRef<Path>, positions: &[TrainingPosition]) -> io::Result<()> {
+    let contents: String = positions.iter()
+        .map(|p| format!("{} {}\n", to_fen(&p.board), p.outcome))
+        .collect();
+    fs::write(path, contents)
+}
+
+pub fn load_positions(path: impl AsRef<Path>) -> io::Result<Vec<TrainingPosition>> {
+    fs::read_to_string(path)?.lines().filter(|l| !l.trim().is_empty()).map(|line| {
+        let (fen, outcome) = line.rsplit_once(' ')
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("bad position line: {}", line)))?;
+        let board = from_fen(fen);
+        let outcome = outcome.trim().parse::<f64>().ok();
+        match (board, outcome) {
+            (Some(board), Some(outcome)) => Ok(TrainingPosition { board, outcome }),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("bad position line: {}", line))),
+        }
+    }).collect()
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1. / (1. + (-x).exp())
+}
+
+// Mean squared difference between game outcomes and the outcome predicted
+// from the evaluation, squashed through a sigmoid with scale `k`.
+fn error(data: &[([i32; FEATURE_COUNT], f64)], weights: &Weights, k: f64) -> f64 {
+    let total: f64 = data.par_iter()
+        .map(|(f, outcome)| (outcome - sigmoid(k * weights.apply(f) as f64)).powi(2))
+        .sum();
+    total / data.len().max(1) as f64
+}
+
+// Texel tuning: first fit the sigmoid scale to the starting weights, then
+// nudge one weight at a time by one unit for as long as the error drops.
+// `report` gets the pass (0 for the starting weights), the scale, the error
+// and the weights after it.
+pub fn tune(positions: &[TrainingPosition], start: Weights, max_passes: usize, mut report: impl FnMut(usize, f64, f64, &Weights)) -> Weights {
+    let data: Vec<([i32; FEATURE_COUNT], f64)> = positions.iter()
+        .map(|p| (features(&p.board), p.outcome))
+        .collect();
+
+    let mut k = 0.01;
+    let mut best_k_error = f64::MAX;
+    for step in 1..=300 {
+        let candidate = step as f64 * 0.005;
+        let candidate_error = error(&data, &start, candidate);
+        if candidate_error < best_k_error {
+            best_k_error = candidate_error;
+            k = candidate;
+        }
+    }
+
+    let mut weights = start;
+    let mut best_error = error(&data, &weights, k);
+    report(0, k, best_error, &weights);
+    for pass in 0..max_passes {
+        let mut improved = false;
+        for index in 0..FEATURE_COUNT {
+            for delta in [1, -1] {
+                let mut candidate = weights;
+                candidate.0[index] += delta;
+                let candidate_error = error(&data, &candidate, k);
+                if candidate_error < best_error {
+                    best_error = candidate_error;
+                    weights = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        report(pass + 1, k, best_error, &weights);
+        if !improved {
+            break;
+        }
+    }
+    weights
+}
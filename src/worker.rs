@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::checkers::{Checkers, CheckersError, Move};
+use crate::evaluator::Evaluator;
+use crate::search::{self, SearchOptions, SearchStats};
+
+pub type SearchResult = Result<(usize, usize, Move), CheckersError>;
+
+// Depth of the quick search that guesses the opponent's move before pondering.
+const PREDICTION_DEPTH: usize = 4;
+
+// Runs `get_best_move` on its own thread so a frame loop can keep drawing and
+// poll for the answer.
+pub struct SearchWorker {
+    receiver: Receiver<SearchResult>,
+    cancel: Arc<AtomicBool>,
+    // The position being searched, once it is known.
+    position: Arc<Mutex<Option<Checkers>>>,
+    stats: Arc<Mutex<Option<SearchStats>>>,
+    deadline: Option<Instant>,
+}
+
+impl SearchWorker {
+    pub fn spawn<E: Evaluator + Send + 'static>(board: Checkers, options: SearchOptions, evaluator: E) -> Self {
+        Self::start(Some(board), options.time_limit().map(|budget| Instant::now() + budget), move |cancel, _, report| {
+            let mut board = board;
+            board.get_best_move_reporting(&SearchOptions { time_budget: None, ..options }, &evaluator, cancel, report)
+        })
+    }
+
+    // Searches on the opponent's time: guesses the move they will play from
+    // `board`, then searches our reply to it with no time limit. If they do
+    // play that move, hand the worker over with `start_clock`; otherwise drop
+    // it.
+    pub fn ponder<E: Evaluator + Send + 'static>(board: Checkers, options: SearchOptions, evaluator: E) -> Self {
+        Self::start(None, None, move |cancel, position, report| {
+            let prediction = SearchOptions::fixed_depth(options.max_depth.min(PREDICTION_DEPTH));
+            let (j, i, m) = search::best_move_cancellable(&board, &prediction, &evaluator, cancel)?;
+            let mut predicted = board;
+            predicted.make_move_from_enum(j, i, &m)?;
+            *position.lock().expect("ponder position lock") = Some(predicted);
+            predicted.get_best_move_reporting(&SearchOptions { time_budget: None, ..options }, &evaluator, cancel, report)
+        })
+    }
+
+    fn start<F>(position: Option<Checkers>, deadline: Option<Instant>, search: F) -> Self
+    where
+        F: FnOnce(&AtomicBool, &Mutex<Option<Checkers>>, &mut dyn FnMut(&SearchStats)) -> SearchResult + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let position = Arc::new(Mutex::new(position));
+        let worker_cancel = Arc::clone(&cancel);
+        let worker_position = Arc::clone(&position);
+        let stats = Arc::new(Mutex::new(None));
+        let worker_stats = Arc::clone(&stats);
+        thread::spawn(move || {
+            let mut report = |latest: &SearchStats| {
+                *worker_stats.lock().expect("search stats lock") = Some(latest.clone());
+            };
+            let result = search(&worker_cancel, &worker_position, &mut report);
+            // the receiver is gone if the worker was abandoned
+            let _ = sender.send(result);
+        });
+        Self { receiver, cancel, position, stats, deadline }
+    }
+
+    // Statistics as of the last finished iteration of the main search.
+    pub fn stats(&self) -> Option<SearchStats> {
+        self.stats.lock().expect("search stats lock").clone()
+    }
+
+    // True if the worker is searching this position, which for a ponder
+    // search means the prediction came true.
+    pub fn is_searching(&self, board: &Checkers) -> bool {
+        match *self.position.lock().expect("ponder position lock") {
+            Some(position) => position.board_state == board.board_state && position.current_turn == board.current_turn,
+            None => false,
+        }
+    }
+
+    // Starts counting `budget` from now; the search is stopped once it runs
+    // out. Time already spent pondering comes for free.
+    pub fn start_clock(&mut self, budget: Option<Duration>) {
+        self.deadline = budget.map(|budget| Instant::now() + budget);
+    }
+
+    // The result once the search is done, without blocking.
+    pub fn poll(&self) -> Option<SearchResult> {
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.cancel();
+        }
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(CheckersError::GameOver)),
+        }
+    }
+
+    // Blocks until the search finishes.
+    pub fn wait(self) -> SearchResult {
+        if let Some(deadline) = self.deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if let Ok(result) = self.receiver.recv_timeout(remaining) {
+                return result;
+            }
+            self.cancel();
+        }
+        self.receiver.recv().unwrap_or(Err(CheckersError::GameOver))
+    }
+
+    // Asks the search to stop; it still answers with the best move found so
+    // far. Dropping the worker after cancelling abandons it entirely.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for SearchWorker {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
@@ -0,0 +1,49 @@
+use hw4::analysis::{analyze_game, annotated_pdn, summary, Judgement, Thresholds};
+use hw4::evaluator::MaterialEvaluator;
+use hw4::game::Game;
+use hw4::pdn::{from_fen, parse_move, parse_pdn, PdnGame};
+use hw4::search::SearchOptions;
+
+// Black can take two men with 8x15x24 and plays 1-6 instead; red's reply
+// is the engine's own choice.
+fn blundered_game() -> Game {
+    let mut game = Game::from_position(from_fen("B:W9,11,19,K32:B1,5,8").unwrap());
+    let (j, i, m) = parse_move(&game.checkers, "1-6").unwrap();
+    game.play(j, i, &m).unwrap();
+    let mut board = game.checkers;
+    let (j, i, m) = board.get_best_move(4, board.current_turn).unwrap();
+    game.play(j, i, &m).unwrap();
+    game
+}
+
+#[test]
+fn a_missed_double_jump_is_a_blunder() {
+    let game = blundered_game();
+    let mut progress = Vec::new();
+    let options = SearchOptions::fixed_depth(4);
+    let analysis = analyze_game(&game, &options, &MaterialEvaluator, &Thresholds::default(), |done, total| progress.push((done, total)));
+    assert_eq!(progress, [(1, 3), (2, 3), (3, 3)]);
+
+    assert_eq!(analysis.len(), 2);
+    let blunder = &analysis[0];
+    assert_eq!(blunder.judgement, Some(Judgement::Blunder));
+    assert!(blunder.loss() >= Thresholds::default().blunder);
+    assert_eq!(blunder.best, parse_move(&game.start, "8x15x24"));
+    // the engine's own move is never flagged
+    assert_eq!(analysis[1].judgement, None);
+    assert_eq!(summary(&game, &analysis), "black: 0 inaccuracies, 0 mistakes, 1 blunders; red: 0 inaccuracies, 0 mistakes, 0 blunders");
+}
+
+#[test]
+fn blunders_are_annotated_in_the_pdn() {
+    let game = blundered_game();
+    let analysis = analyze_game(&game, &SearchOptions::fixed_depth(4), &MaterialEvaluator, &Thresholds::default(), |_, _| ());
+    let record = PdnGame::from_game(&game);
+    let text = annotated_pdn(&record, &analysis);
+    assert!(text.contains("1. 1-6 ?? {blunder: 8x15x24 was better ("), "{}", text);
+
+    // the annotations are comments, so the moves read back unchanged
+    let parsed = parse_pdn(&text);
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].moves, record.moves);
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use std::fs;
+
+use hw4::book::OpeningBook;
+use hw4::evaluator::MaterialEvaluator;
+use hw4::pdn::{from_fen, move_to_notation, START_FEN};
+use hw4::search::SearchOptions;
+
+use common::temp_path;
+
+const GAMES: &str = "\
+[Event \"first\"]
+1. 11-15 23-19 2. 8-11 22-17 1-0
+
+[Event \"second\"]
+1. 11-15 24-20 {a side line} 2. 15-18 0-1
+";
+
+#[test]
+fn books_are_built_from_pdn_games() {
+    let opening_book = OpeningBook::from_pdn(GAMES, 2);
+    let start = from_fen(START_FEN).unwrap();
+    // both games open 11-15, and black won the first one
+    assert_eq!(opening_book.moves(&start), Some(&[("11-15".to_string(), 3)][..]));
+
+    let path = temp_path("book.txt");
+    opening_book.save(&path).unwrap();
+    let loaded = OpeningBook::load(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.len(), opening_book.len());
+    assert_eq!(loaded.moves(&start), opening_book.moves(&start));
+}
+
+#[test]
+fn the_book_answers_before_the_search() {
+    let mut opening_book = OpeningBook::new();
+    // legal but not what a search would pick, and not a legal move at all
+    let start = from_fen(START_FEN).unwrap();
+    opening_book.add(&start, "9-13", 1);
+    opening_book.add(&start, "22-18", 5);
+    let options = SearchOptions { book: Some(Box::leak(Box::new(opening_book))), ..SearchOptions::fixed_depth(1) };
+
+    let mut board = start;
+    let (j, i, m) = board.get_best_move_with_options(&options, &MaterialEvaluator).unwrap();
+    assert_eq!(move_to_notation(j, i, &m), "9-13");
+    // without the book the start is searched like any other position
+    let (j, i, m) = board.get_best_move_with_options(&SearchOptions::fixed_depth(1), &MaterialEvaluator).unwrap();
+    assert_ne!(move_to_notation(j, i, &m), "9-13");
+
+    // positions off the book are searched
+    let mut after = from_fen("W:W21-32:B1-8,10-13").unwrap();
+    assert!(after.get_best_move_with_options(&options, &MaterialEvaluator).is_ok());
+}
@@ -0,0 +1,35 @@
+use hw4::evaluator::MaterialEvaluator;
+use hw4::pdn::{from_fen, move_to_notation, square_coords};
+use hw4::search::{best_move, SearchOptions};
+
+// Black's double jump 8x15x24 takes two men; 5x14 takes one.
+const SHOT: &str = "B:W9,11,19,K32:B1,5,8";
+
+fn squares(numbers: &[usize]) -> Vec<(usize, usize)> {
+    numbers.iter().map(|&number| square_coords(number).unwrap()).collect()
+}
+
+#[test]
+fn paths_visit_every_landing_square() {
+    let board = from_fen(SHOT).unwrap();
+    let (j, i, m) = board.get_all_moves().into_iter().find(|(j, i, m)| move_to_notation(*j, *i, m) == "8x15x24").unwrap();
+    assert_eq!(m.path(j, i), squares(&[8, 15, 24]));
+
+    let (j, i, m) = board.get_all_moves().into_iter().find(|(j, i, m)| move_to_notation(*j, *i, m) == "1-6").unwrap();
+    assert_eq!(m.path(j, i), squares(&[1, 6]));
+}
+
+#[test]
+fn only_the_jumping_piece_moves_partway_through_a_multi_jump() {
+    let mut board = from_fen(SHOT).unwrap();
+    let (j, i) = square_coords(8).unwrap();
+    let (to_j, to_i) = square_coords(15).unwrap();
+    board.make_move(j, i, to_j, to_i).unwrap();
+    assert_eq!(board.required_square, Some((to_j, to_i)));
+
+    let moves: Vec<String> = board.get_all_moves().iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect();
+    assert_eq!(moves, ["15x24"]);
+    // so a hint asked for now finishes the jump
+    let (j, i, m) = best_move(&board, &SearchOptions::fixed_depth(4), &MaterialEvaluator).unwrap();
+    assert_eq!(m.path(j, i), squares(&[15, 24]));
+}
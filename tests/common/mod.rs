@@ -0,0 +1,7 @@
+use std::env;
+use std::path::PathBuf;
+
+// A file in the temp directory that no other test run writes to.
+pub fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("hw4-{}-{}", std::process::id(), name))
+}
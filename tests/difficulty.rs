@@ -0,0 +1,40 @@
+use hw4::difficulty::Difficulty;
+use hw4::settings::Settings;
+
+#[test]
+fn levels_parse_by_name() {
+    for difficulty in Difficulty::ALL {
+        assert_eq!(difficulty.to_string().parse(), Ok(difficulty));
+    }
+    assert_eq!(" Expert ".parse(), Ok(Difficulty::Expert));
+    assert_eq!("impossible".parse::<Difficulty>(), Err("unknown difficulty impossible".to_string()));
+}
+
+#[test]
+fn harder_levels_search_longer_and_err_less() {
+    let options: Vec<_> = Difficulty::ALL.iter().map(|d| d.search_options(1)).collect();
+    for pair in options.windows(2) {
+        let (easier, harder) = (&pair[0], &pair[1]);
+        assert!(harder.max_depth > easier.max_depth);
+        assert!(harder.time_budget > easier.time_budget);
+        assert!(harder.eval_noise <= easier.eval_noise);
+        assert!(harder.multi_pv <= easier.multi_pv);
+        assert!(harder.blunder_chance <= easier.blunder_chance);
+    }
+
+    // hard is the old fixed seven ply search with no mistakes
+    let hard = Difficulty::Hard.search_options(1);
+    assert_eq!(hard.max_depth, 7);
+    assert_eq!((hard.eval_noise, hard.multi_pv, hard.blunder_chance), (0, 1, 0.));
+    assert!(Difficulty::Beginner.search_options(1).blunder_chance > 0.);
+}
+
+#[test]
+fn levels_search_on_the_configured_threads() {
+    assert_eq!(Difficulty::Medium.search_options(6).threads, 6);
+    let settings = Settings { threads: 3, seed: Some(9), difficulty: Difficulty::Easy, ..Settings::default() };
+    let options = settings.search_options();
+    assert_eq!(options.threads, 3);
+    assert_eq!(options.deterministic_seed, Some(9));
+    assert_eq!(options.max_depth, Difficulty::Easy.search_options(1).max_depth);
+}
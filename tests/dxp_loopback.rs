@@ -0,0 +1,111 @@
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hw4::dxp::{DxpEvent, DxpMessage, DxpSession, EndReason};
+use hw4::evaluator::MaterialEvaluator;
+use hw4::game::GameResult;
+use hw4::pdn::{from_fen, move_to_notation};
+use hw4::player::{AlphaBetaPlayer, GreedyPlayer, Player, RandomPlayer};
+
+fn notation(session: &DxpSession) -> Vec<String> {
+    session.game.history.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect()
+}
+
+// Starts a follower on a free local port that plays with a seeded random
+// player, returning the address and a handle to its result and moves.
+fn follower(seed: u64) -> (String, thread::JoinHandle<(GameResult, Vec<String>, i32)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut session = DxpSession::accept(stream, "follower").unwrap();
+        let result = session.play(&mut RandomPlayer::with_seed(seed)).unwrap();
+        (result, notation(&session), session.color)
+    });
+    (address, handle)
+}
+
+#[test]
+fn messages_round_trip() {
+    let board = from_fen("W:WK3,22:B9,K30").unwrap();
+    let messages = [
+        DxpMessage::Chat("hello".to_string()),
+        DxpMessage::GameRequest { name: "engine".to_string(), follower_color: -1, minutes: 10, moves: 50, position: None },
+        DxpMessage::GameRequest { name: "engine".to_string(), follower_color: 1, minutes: 5, moves: 40, position: Some(Box::new(board)) },
+        DxpMessage::GameAccept { name: "other".to_string(), code: '0' },
+        DxpMessage::Move { seconds: 12, from: 22, to: 6, captured: vec![18, 10] },
+        DxpMessage::GameEnd { reason: EndReason::Win, stop: true },
+        DxpMessage::BackRequest { move_number: 7, color: -1 },
+        DxpMessage::BackAccept { code: '0' },
+    ];
+    for message in messages {
+        let text = message.encode();
+        assert_eq!(DxpMessage::parse(&text).unwrap().encode(), text);
+    }
+    assert!(DxpMessage::parse("M00").is_err());
+    assert!(DxpMessage::parse("X").is_err());
+}
+
+#[test]
+fn two_engines_finish_a_game() {
+    let (address, handle) = follower(7);
+    let stream = TcpStream::connect(&address).unwrap();
+    let mut session = DxpSession::initiate(stream, "initiator", 1, 1, 50, None).unwrap();
+    assert_eq!(session.opponent, "follower");
+    let result = session.play(&mut AlphaBetaPlayer::new(3)).unwrap();
+    let (their_result, their_moves, their_color) = handle.join().unwrap();
+    assert_eq!(their_color, -1);
+    assert_eq!(result, their_result);
+    assert_eq!(notation(&session), their_moves);
+    assert!(!their_moves.is_empty());
+}
+
+#[test]
+fn take_back_rewinds_both_sides() {
+    let (address, handle) = follower(11);
+    let stream = TcpStream::connect(&address).unwrap();
+    let mut session = DxpSession::initiate(stream, "initiator", 1, 1, 50, None).unwrap();
+    let (j, i, m) = session.game.legal_moves().remove(0);
+    session.send_move(j, i, &m, Duration::ZERO).unwrap();
+    assert!(matches!(session.receive().unwrap(), DxpEvent::Moved(..)));
+    assert_eq!(session.game.history.len(), 2);
+
+    assert!(session.request_back(1, 1).unwrap());
+    assert!(session.game.history.is_empty());
+    // a move number past the game is refused and changes nothing
+    assert!(!session.request_back(5, 1).unwrap());
+
+    let result = session.play(&mut GreedyPlayer::with_seed(3)).unwrap();
+    let (their_result, their_moves, _) = handle.join().unwrap();
+    assert_eq!(result, their_result);
+    assert_eq!(notation(&session), their_moves);
+}
+
+#[test]
+fn moves_keep_to_the_time_control() {
+    let (address, handle) = follower(5);
+    let stream = TcpStream::connect(&address).unwrap();
+    // a minute for 200 moves is 0.3 seconds each
+    let mut session = DxpSession::initiate(stream, "initiator", 1, 1, 200, None).unwrap();
+    let budget = session.time_per_move();
+    assert_eq!(budget, Duration::from_millis(300));
+    // far too deep to finish, so only the clock stops the search
+    let options = session.search_options(30);
+    assert_eq!(options.time_budget, Some(budget));
+    let mut player = AlphaBetaPlayer::with_options(options, Box::new(MaterialEvaluator));
+
+    for _ in 0..3 {
+        let start = Instant::now();
+        let (j, i, m) = player.choose_move(&session.game).unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed < budget + Duration::from_millis(500), "took {:?}", elapsed);
+        session.send_move(j, i, &m, elapsed).unwrap();
+        assert!(matches!(session.receive().unwrap(), DxpEvent::Moved(..)));
+    }
+
+    let result = session.play(&mut GreedyPlayer::with_seed(1)).unwrap();
+    let (their_result, their_moves, _) = handle.join().unwrap();
+    assert_eq!(result, their_result);
+    assert_eq!(notation(&session), their_moves);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::fs;
+
+use hw4::checkers::Checkers;
+use hw4::evaluator::{features, Evaluator, WeightedEvaluator, Weights, FEATURE_COUNT};
+use hw4::pdn::{from_fen, START_FEN};
+
+use common::temp_path;
+
+// The same position with the colors swapped and the board turned around.
+fn mirrored(board: &Checkers) -> Checkers {
+    let mut board_state = [[0; 8]; 8];
+    for j in 0..8 {
+        for i in 0..8 {
+            board_state[7 - j][7 - i] = -board.board_state[j][i];
+        }
+    }
+    Checkers::from_position(board_state, -board.current_turn)
+}
+
+#[test]
+fn the_evaluation_favours_neither_color() {
+    assert_eq!(features(&from_fen(START_FEN).unwrap()), [0; FEATURE_COUNT]);
+
+    let evaluator = WeightedEvaluator::default();
+    for fen in ["B:W18,22,K30:B1,K14,15,9", "W:W10,18,K27:B14,K23,5"] {
+        let board = from_fen(fen).unwrap();
+        assert_ne!(evaluator.evaluate(&board), 0, "{}", fen);
+        assert_eq!(evaluator.evaluate(&mirrored(&board)), -evaluator.evaluate(&board), "{}", fen);
+    }
+}
+
+#[test]
+fn the_report_adds_up_to_the_evaluation() {
+    let evaluator = WeightedEvaluator::default();
+    let board = from_fen("B:W18,22,K30:B1,K14,15,9").unwrap();
+    let report = evaluator.report(&board);
+    assert_eq!(report.len(), FEATURE_COUNT);
+    assert_eq!(report.iter().map(|term| term.score()).sum::<i32>(), evaluator.evaluate(&board));
+}
+
+#[test]
+fn weights_survive_a_save_and_load() {
+    let path = temp_path("weights.txt");
+    let weights = Weights([7, 2, -3, 4, 0, 2, -1, 5, 6, -8]);
+    weights.save(&path).unwrap();
+    assert_eq!(Weights::load(&path).unwrap(), weights);
+
+    // names left out keep their default
+    fs::write(&path, "material=9\n").unwrap();
+    let mut expected = Weights::default();
+    expected.0[0] = 9;
+    assert_eq!(Weights::load(&path).unwrap(), expected);
+
+    fs::write(&path, "material=lots\n").unwrap();
+    assert!(Weights::load(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
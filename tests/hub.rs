@@ -0,0 +1,106 @@
+use std::io::{self, Write};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hw4::book::OpeningBook;
+use hw4::evaluator::WeightedEvaluator;
+use hw4::hub::{from_hub_position, run, to_hub_position, HubCommand, HubEngine};
+use hw4::pdn::{from_fen, START_FEN};
+
+// Output shared with the test while `run` writes to it on another thread.
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Shared {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().map(str::to_string).collect()
+    }
+}
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn engine() -> HubEngine {
+    HubEngine::new(Arc::new(WeightedEvaluator::default()))
+}
+
+// Polls `engine` until it sends `done`, returning everything it wrote.
+fn search(engine: &mut HubEngine) -> Vec<String> {
+    let mut out = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !String::from_utf8_lossy(&out).contains("done") {
+        assert!(Instant::now() < deadline, "timed out");
+        engine.poll(&mut out).unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+    String::from_utf8(out).unwrap().lines().map(str::to_string).collect()
+}
+
+fn booked_engine() -> HubEngine {
+    let mut opening_book = OpeningBook::new();
+    opening_book.add(&from_fen(START_FEN).unwrap(), "11-15", 1);
+    engine().with_book(Some(Box::leak(Box::new(opening_book))))
+}
+
+#[test]
+fn commands_and_positions_parse() {
+    let command = HubCommand::parse("pos pos=Bbbbbbbbbbbbbeeeeeeeewwwwwwwwwwww moves=\"11-15 23-19\"").unwrap();
+    assert_eq!(command.name, "pos");
+    assert_eq!(command.get("moves"), Some("11-15 23-19"));
+    assert!(HubCommand::parse("go think").unwrap().has("think"));
+    assert_eq!(HubCommand::parse("  "), None);
+
+    let start = from_fen(START_FEN).unwrap();
+    let text = to_hub_position(&start);
+    assert_eq!(text, "Bbbbbbbbbbbbbeeeeeeeewwwwwwwwwwww");
+    assert_eq!(to_hub_position(&from_hub_position(&text).unwrap()), text);
+    assert!(from_hub_position("Bbbb").is_none());
+}
+
+#[test]
+fn a_book_move_comes_back_through_the_protocol() {
+    let (sender, receiver) = mpsc::channel();
+    let out = Shared::default();
+    let mut writer = out.clone();
+    let session = thread::spawn(move || run(receiver, &mut writer, booked_engine()));
+    for line in ["hub", "init", "new-game", "go think"] {
+        sender.send(line.to_string()).unwrap();
+    }
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !out.lines().iter().any(|line| line.starts_with("done")) {
+        assert!(Instant::now() < deadline, "timed out, got {:?}", out.lines());
+        thread::sleep(Duration::from_millis(5));
+    }
+    sender.send("quit".to_string()).unwrap();
+    session.join().unwrap().unwrap();
+
+    let lines = out.lines();
+    assert!(lines.contains(&"ready".to_string()));
+    assert_eq!(lines.last().map(String::as_str), Some("done move=11-15"));
+}
+
+#[test]
+fn the_last_info_line_names_the_move_played() {
+    let mut engine = booked_engine();
+    let mut out = Vec::new();
+    // out of the book, with nothing but depth to stop the search
+    for line in ["pos moves=\"11-15 23-19 8-11 22-17\"", "set-param name=threads value=1", "level depth=5", "go think"] {
+        engine.handle(line, &mut out).unwrap();
+    }
+    assert!(out.is_empty(), "{}", String::from_utf8_lossy(&out));
+    let lines = search(&mut engine);
+    let done = lines.last().unwrap().strip_prefix("done move=").unwrap().to_string();
+    let info = lines.iter().rev().find(|line| line.starts_with("info")).unwrap();
+    assert!(info.starts_with("info depth=5"), "{}", info);
+    assert!(info.ends_with(&format!("pv={}", done)), "{} then {}", info, done);
+}
@@ -0,0 +1,135 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hw4::checkers::Checkers;
+use hw4::lan::{LanMessage, LanSession, LanStatus, LanUpdate, PROTOCOL_VERSION};
+use hw4::pdn::{from_fen, move_to_notation, parse_move, START_FEN};
+
+fn start() -> Checkers {
+    from_fen(START_FEN).unwrap()
+}
+
+fn local_address(host: &LanSession) -> String {
+    let port = host.address.rsplit(':').next().unwrap();
+    format!("127.0.0.1:{}", port)
+}
+
+// Polls both sides until `done` holds, collecting what each one saw.
+fn poll_until(sessions: &mut [&mut LanSession], mut done: impl FnMut(&[&mut LanSession]) -> bool) -> Vec<Vec<LanUpdate>> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut seen = vec![Vec::new(); sessions.len()];
+    while !done(sessions) {
+        assert!(Instant::now() < deadline, "timed out");
+        for (session, updates) in sessions.iter_mut().zip(&mut seen) {
+            updates.extend(session.poll());
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    seen
+}
+
+// Makes the move written as `notation` for the side whose turn it is.
+fn play(session: &mut LanSession, notation: &str) {
+    let mut board = session.game.checkers;
+    let (j, i, m) = parse_move(&board, notation).unwrap();
+    board.make_move_from_enum(j, i, &m).unwrap();
+    session.play_to(&board).unwrap();
+}
+
+fn history(session: &LanSession) -> Vec<String> {
+    session.game.history.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect()
+}
+
+#[test]
+fn messages_round_trip() {
+    let messages = [
+        LanMessage::Hello { version: PROTOCOL_VERSION },
+        LanMessage::Game { guest_color: -1, start: START_FEN.to_string(), moves: vec!["11-15".to_string(), "23-19".to_string()] },
+        LanMessage::Move { ply: 4, notation: "15x22x29".to_string() },
+        LanMessage::Reject { ply: 4, reason: "not your turn".to_string() },
+    ];
+    for message in messages {
+        assert_eq!(LanMessage::parse(&message.to_line()), Some(message));
+    }
+    assert_eq!(LanMessage::parse("move x 11-15"), None);
+}
+
+#[test]
+fn host_and_guest_exchange_moves() {
+    let mut host = LanSession::host(0, 1, start()).unwrap();
+    let mut guest = LanSession::join(&local_address(&host));
+    poll_until(&mut [&mut host, &mut guest], |s| s[1].color != 0);
+    assert_eq!(guest.color, -1);
+    assert!(host.can_move());
+    assert!(!guest.can_move());
+
+    play(&mut host, "11-15");
+    // not the host's turn any more, so a second move is refused locally
+    assert!(host.play_to(&host.game.checkers.clone()).is_err());
+    let seen = poll_until(&mut [&mut host, &mut guest], |s| s[1].can_move());
+    assert!(matches!(seen[1].last(), Some(LanUpdate::Moved(..))));
+
+    play(&mut guest, "23-19");
+    poll_until(&mut [&mut host, &mut guest], |s| s[0].can_move());
+    assert_eq!(history(&host), ["11-15", "23-19"]);
+    assert_eq!(history(&guest), history(&host));
+}
+
+#[test]
+fn illegal_moves_are_rejected() {
+    let mut host = LanSession::host(0, 1, start()).unwrap();
+    let stream = TcpStream::connect(local_address(&host)).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    writeln!(writer, "hello {}", PROTOCOL_VERSION).unwrap();
+    let mut read_line = |host: &mut LanSession| {
+        let mut line = String::new();
+        while !line.ends_with('\n') {
+            host.poll();
+            reader.get_ref().set_read_timeout(Some(Duration::from_millis(20))).unwrap();
+            let _ = reader.read_line(&mut line);
+        }
+        line
+    };
+    assert!(read_line(&mut host).starts_with("game red"));
+
+    // red may not move first, and the host answers with its game
+    writeln!(writer, "move 0 22-18").unwrap();
+    assert!(read_line(&mut host).starts_with("reject 0"));
+    assert!(read_line(&mut host).starts_with("game red"));
+    assert!(host.game.history.is_empty());
+
+    // right turn and ply, but not a legal move
+    play(&mut host, "11-15");
+    assert!(read_line(&mut host).starts_with("move 0 11-15"));
+    writeln!(writer, "move 1 15-11").unwrap();
+    assert!(read_line(&mut host).starts_with("reject 1"));
+    assert_eq!(host.game.history.len(), 1);
+}
+
+#[test]
+fn a_dropped_guest_resumes_the_game() {
+    let mut host = LanSession::host(0, -1, start()).unwrap();
+    let address = local_address(&host);
+    let mut guest = LanSession::join(&address);
+    poll_until(&mut [&mut host, &mut guest], |s| s[1].can_move());
+    play(&mut guest, "9-13");
+    poll_until(&mut [&mut host, &mut guest], |s| s[0].can_move());
+    play(&mut host, "22-18");
+    poll_until(&mut [&mut host, &mut guest], |s| s[1].can_move());
+
+    drop(guest);
+    poll_until(&mut [&mut host], |s| s[0].status == LanStatus::Disconnected);
+    assert!(!host.can_move());
+
+    let mut guest = LanSession::join(&address);
+    let seen = poll_until(&mut [&mut host, &mut guest], |s| s[1].can_move());
+    assert!(seen[1].contains(&LanUpdate::Synced));
+    assert_eq!(guest.color, 1);
+    assert_eq!(history(&guest), ["9-13", "22-18"]);
+    play(&mut guest, "13-17");
+    poll_until(&mut [&mut host, &mut guest], |s| s[0].can_move());
+    assert_eq!(history(&host), history(&guest));
+}
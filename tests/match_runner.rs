@@ -0,0 +1,31 @@
+use hw4::match_runner::{run_match, MatchConfig, MatchScore, Sprt, SprtResult};
+
+const SPRT: Sprt = Sprt { elo0: 0., elo1: 10., alpha: 0.05, beta: 0.05 };
+
+#[test]
+fn one_sided_scores_still_have_a_likelihood_ratio() {
+    let all_wins = MatchScore { wins: 20, draws: 0, losses: 0 };
+    assert!(all_wins.llr(SPRT.elo0, SPRT.elo1) >= SPRT.bounds().1);
+    assert_eq!(SPRT.check(&all_wins), SprtResult::AcceptH1);
+
+    let all_losses = MatchScore { wins: 0, draws: 0, losses: 20 };
+    assert_eq!(SPRT.check(&all_losses), SprtResult::AcceptH0);
+
+    let drawless = MatchScore { wins: 3, draws: 0, losses: 3 };
+    assert_eq!(SPRT.check(&drawless), SprtResult::Continue);
+    assert_eq!(MatchScore::default().llr(SPRT.elo0, SPRT.elo1), 0.);
+}
+
+#[test]
+fn sprt_stops_a_one_sided_match_early() {
+    let config = MatchConfig {
+        first: "ab:depth=2".parse().unwrap(),
+        second: "random".parse().unwrap(),
+        games: 200,
+        max_plies: 200,
+        sprt: Some(SPRT),
+    };
+    let (score, decision) = run_match(&config, |_| ()).unwrap();
+    assert_eq!(decision, Some(SprtResult::AcceptH1));
+    assert!(score.games() < 200, "played all {} games", score.games());
+}
@@ -0,0 +1,33 @@
+use hw4::game::{play_game_from, Game, GameResult};
+use hw4::mcts::MctsPlayer;
+use hw4::pdn::{from_fen, move_to_notation, START_FEN};
+use hw4::player::{Player, RandomPlayer};
+
+fn player(playouts: usize, seed: u64) -> MctsPlayer {
+    let mut player = MctsPlayer::with_seed(playouts, seed);
+    player.threads = 2;
+    player
+}
+
+#[test]
+fn mcts_only_plays_legal_moves() {
+    let start = Game::from_position(from_fen(START_FEN).unwrap());
+    // `Game::play` refuses illegal moves, so any would end the game in an error
+    let (_, game) = play_game_from(start, &mut player(50, 1), &mut RandomPlayer::with_seed(2), 60).unwrap();
+    assert!(!game.history.is_empty());
+}
+
+#[test]
+fn mcts_finds_a_win_in_one() {
+    // 7-11 shuts the red king in its corner; everything else lets it out
+    let game = Game::from_position(from_fen("B:WK4:B7,8").unwrap());
+    for seed in 0..3 {
+        let (j, i, m) = player(200, seed).choose_move(&game).unwrap();
+        assert_eq!(move_to_notation(j, i, &m), "7-11", "seed {}", seed);
+    }
+
+    let mut won = game.clone();
+    let (j, i, m) = player(200, 0).choose_move(&game).unwrap();
+    won.play(j, i, &m).unwrap();
+    assert_eq!(won.result(), Some(GameResult::BlackWon));
+}
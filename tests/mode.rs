@@ -0,0 +1,68 @@
+mod common;
+
+use std::fs;
+
+use hw4::difficulty::Difficulty;
+use hw4::evaluator::{EvaluatorKind, MaterialEvaluator};
+use hw4::mode::GameMode;
+use hw4::pdn::{from_fen, START_FEN};
+use hw4::search::SearchOptions;
+use hw4::settings::Settings;
+
+use common::temp_path;
+
+#[test]
+fn modes_parse_by_name() {
+    for mode in GameMode::ALL {
+        assert_eq!(mode.to_string().parse(), Ok(mode));
+    }
+    assert_eq!(" AI-vs-AI ".parse(), Ok(GameMode::AiVsAi));
+    assert_eq!("solo".parse::<GameMode>(), Err("unknown game mode solo".to_string()));
+}
+
+#[test]
+fn the_engine_plays_the_other_side() {
+    let (black, red) = (1, -1);
+    let ai = |mode: GameMode| (mode.is_ai(black), mode.is_ai(red));
+    assert_eq!(ai(GameMode::HotSeat), (false, false));
+    assert_eq!(ai(GameMode::HumanBlack), (false, true));
+    assert_eq!(ai(GameMode::HumanRed), (true, false));
+    assert_eq!(ai(GameMode::AiVsAi), (true, true));
+    // puzzle replies are scripted
+    assert_eq!(ai(GameMode::Puzzle), (false, false));
+}
+
+#[test]
+fn settings_survive_a_save_and_load() {
+    let settings = Settings {
+        difficulty: Difficulty::Expert,
+        ponder: false,
+        threads: 3,
+        seed: Some(42),
+        evaluator: EvaluatorKind::Neural,
+        mode: GameMode::HumanRed,
+    };
+    let path = temp_path("settings.txt");
+    settings.save(&path).unwrap();
+    let loaded = Settings::load(&path);
+    fs::write(&path, "mode=sideways\nseed=many\nthreads=0\n").unwrap();
+    let bad = Settings::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, settings);
+
+    // unknown values fall back to the defaults
+    assert_eq!(bad.mode, GameMode::default());
+    assert_eq!(bad.seed, None);
+    assert_eq!(bad.threads, 1);
+    assert_eq!(Settings::load(&path), Settings::default());
+}
+
+#[test]
+fn the_engine_opens_as_black() {
+    let mut board = from_fen(START_FEN).unwrap();
+    assert!(GameMode::HumanRed.is_ai(board.current_turn));
+    board.make_ai_move(&SearchOptions::fixed_depth(3), &MaterialEvaluator).unwrap();
+    assert!(board.current_turn < 0);
+    assert_eq!(board.board_state.iter().flatten().filter(|&&piece| piece > 0).count(), 12);
+    assert_ne!(board.board_state, from_fen(START_FEN).unwrap().board_state);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use std::fs;
+
+use hw4::evaluator::Evaluator;
+use hw4::nn::{train, Network, NeuralEvaluator, TrainingOptions, DEFAULT_HIDDEN};
+use hw4::pdn::from_fen;
+use hw4::tuning::TrainingPosition;
+
+use common::temp_path;
+
+#[test]
+fn networks_survive_a_save_and_load() {
+    let network = Network::new(&DEFAULT_HIDDEN, 3);
+    let path = temp_path("nn.txt");
+    network.save(&path).unwrap();
+    let loaded = Network::load(&path);
+    fs::write(&path, "checkers-nn 1\n64 8 1\n0.5\n").unwrap();
+    let truncated = Network::load(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), network);
+    assert!(truncated.is_err());
+}
+
+#[test]
+fn training_learns_that_more_men_win() {
+    // black a man up wins, red a man up wins, level positions are drawn
+    let positions: Vec<TrainingPosition> = [
+        ("B:W21,22:B1,2,3", 1.),
+        ("W:W25,30:B5,6,7", 1.),
+        ("B:W21,22,23:B1,2", 0.),
+        ("W:W25,30,31:B5,6", 0.),
+        ("B:W21,22:B1,2", 0.5),
+        ("W:W29,30:B9,10", 0.5),
+    ].iter().map(|(fen, outcome)| TrainingPosition { board: from_fen(fen).unwrap(), outcome: *outcome }).collect();
+
+    let mut network = Network::new(&[8], 1);
+    let options = TrainingOptions { epochs: 200, batch_size: 4, validation_share: 0., ..TrainingOptions::default() };
+    let mut losses = Vec::new();
+    train(&mut network, &positions, &options, |_, loss, _| losses.push(loss));
+    assert_eq!(losses.len(), 200);
+    assert!(losses[199] < losses[0] / 2., "{} then {}", losses[0], losses[199]);
+
+    let evaluator = NeuralEvaluator::new(network);
+    let ahead = evaluator.evaluate(&from_fen("B:W21,22:B1,2,3").unwrap());
+    let behind = evaluator.evaluate(&from_fen("B:W21,22,23:B1,2").unwrap());
+    assert!(ahead > behind, "{} and {}", ahead, behind);
+}
@@ -0,0 +1,50 @@
+use hw4::game::Game;
+use hw4::pdn::{from_fen, move_to_notation, parse_move, parse_pdn, to_fen, PdnGame, START_FEN};
+
+#[test]
+fn fen_round_trips() {
+    for fen in ["W:W18,K30:B1,K14,15", "B:WK4:B8,11"] {
+        assert_eq!(to_fen(&from_fen(fen).unwrap()), fen);
+    }
+    let start = from_fen(START_FEN).unwrap();
+    assert_eq!(from_fen(&to_fen(&start)).unwrap().board_state, start.board_state);
+    // quotes and ranges are accepted on the way in
+    assert_eq!(to_fen(&from_fen("\"B:W21-23:B1-2\"").unwrap()), "B:W21,22,23:B1,2");
+    assert!(from_fen("X:W21:B1").is_none());
+    assert!(from_fen("B:W33:B1").is_none());
+}
+
+#[test]
+fn every_legal_move_reads_back_from_its_notation() {
+    let board = from_fen("B:W10,18,K27:B14,K23").unwrap();
+    let moves = board.get_all_moves();
+    assert!(!moves.is_empty());
+    for (j, i, m) in moves {
+        let notation = move_to_notation(j, i, &m);
+        assert_eq!(parse_move(&board, &notation), Some((j, i, m)), "{}", notation);
+    }
+}
+
+#[test]
+fn games_round_trip_through_pdn() {
+    let mut game = Game::from_position(from_fen(START_FEN).unwrap());
+    for notation in ["11-15", "23-19", "8-11", "22-17", "15-18"] {
+        let (j, i, m) = parse_move(&game.checkers, notation).unwrap();
+        game.play(j, i, &m).unwrap();
+    }
+    let text = PdnGame::from_game(&game).to_pdn();
+    let games = parse_pdn(&text);
+    assert_eq!(games.len(), 1);
+    let replayed = games[0].replay();
+    assert_eq!(replayed.history, game.history);
+    assert_eq!(to_fen(&replayed.checkers), to_fen(&game.checkers));
+
+    // a game from a set up position keeps it in a FEN tag
+    let start = from_fen("W:W18,K30:B1,K14,15").unwrap();
+    let mut game = Game::from_position(start);
+    let (j, i, m) = game.legal_moves()[0].clone();
+    game.play(j, i, &m).unwrap();
+    let pdn_game = &parse_pdn(&PdnGame::from_game(&game).to_pdn())[0];
+    assert_eq!(pdn_game.tag("FEN"), Some("W:W18,K30:B1,K14,15"));
+    assert_eq!(to_fen(&pdn_game.replay().checkers), to_fen(&game.checkers));
+}
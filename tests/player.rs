@@ -0,0 +1,57 @@
+use std::io::{self, Cursor};
+
+use hw4::game::{play_game, play_game_from, Game, GameResult};
+use hw4::pdn::{from_fen, move_to_notation};
+use hw4::player::{AlphaBetaPlayer, GreedyPlayer, HumanPlayer, Player, RandomPlayer};
+
+#[test]
+fn random_players_only_play_legal_moves() {
+    for seed in 0..5 {
+        // `Game::play` refuses illegal moves, so any would end the game in an error
+        let (result, game) = play_game(&mut RandomPlayer::with_seed(seed), &mut RandomPlayer::with_seed(seed + 100), 300).unwrap();
+        assert!(!game.history.is_empty());
+        match result {
+            GameResult::Draw => assert_eq!(game.history.len(), 300),
+            decided => assert_eq!(game.result(), Some(decided)),
+        }
+    }
+}
+
+#[test]
+fn greedy_players_take_the_most_men() {
+    // 8x15x24 takes two men, 5x14 only one
+    let game = Game::from_position(from_fen("B:W9,11,19,K32:B1,5,8").unwrap());
+    for seed in 0..10 {
+        let (j, i, m) = GreedyPlayer::with_seed(seed).choose_move(&game).unwrap();
+        assert_eq!(move_to_notation(j, i, &m), "8x15x24");
+    }
+}
+
+#[test]
+fn games_end_with_a_result() {
+    for seed in 0..3 {
+        let (result, game) = play_game(&mut AlphaBetaPlayer::new(4), &mut RandomPlayer::with_seed(seed), 200).unwrap();
+        assert_eq!(result, GameResult::BlackWon, "seed {}", seed);
+        assert!(game.legal_moves().is_empty());
+        assert!(game.checkers.current_turn < 0);
+    }
+
+    // a game that is over before it starts
+    let over = Game::from_position(from_fen("W:WK4:B8,11").unwrap());
+    let (result, game) = play_game_from(over, &mut RandomPlayer::with_seed(0), &mut RandomPlayer::with_seed(1), 10).unwrap();
+    assert_eq!(result, GameResult::BlackWon);
+    assert!(game.history.is_empty());
+}
+
+#[test]
+fn humans_are_asked_again_after_an_illegal_move() {
+    let game = Game::from_position(from_fen("B:W9,11,19,K32:B1,5,8").unwrap());
+    // first a jump the man on 5 cannot make, then 8x15x24 landing by landing
+    let input = Cursor::new(b"6 7 4 4\n6 1 4 3 2 1\n".to_vec());
+    let (j, i, m) = HumanPlayer::new(Box::new(input), Box::new(io::sink())).choose_move(&game).unwrap();
+    assert_eq!(move_to_notation(j, i, &m), "8x15x24");
+
+    // input running out ends the game
+    let empty = Cursor::new(Vec::new());
+    assert!(HumanPlayer::new(Box::new(empty), Box::new(io::sink())).choose_move(&game).is_err());
+}
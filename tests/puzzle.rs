@@ -0,0 +1,61 @@
+mod common;
+
+use std::fs;
+
+use hw4::pdn::{from_fen, square_coords, START_FEN};
+use hw4::puzzle::{find_puzzle, load_puzzles, save_puzzles, Puzzle, PuzzleOptions, PuzzleSession};
+
+use common::temp_path;
+
+// Black's double jump takes two men; the single jump 5x14 takes one.
+const SHOT: &str = "B:W9,11,19,K32:B1,5,8";
+
+fn square(number: usize) -> (usize, usize) {
+    square_coords(number).unwrap()
+}
+
+#[test]
+fn shots_are_found_and_quiet_positions_skipped() {
+    let puzzle = find_puzzle(&from_fen(SHOT).unwrap(), &PuzzleOptions::default()).unwrap();
+    assert_eq!(puzzle.to_line(), format!("{} 8x15x24", SHOT));
+    assert_eq!(puzzle.side_to_move(), "black");
+    assert!(find_puzzle(&from_fen(START_FEN).unwrap(), &PuzzleOptions::default()).is_none());
+}
+
+#[test]
+fn puzzle_lines_are_checked() {
+    let puzzle = Puzzle::parse(&format!("{} 8x15x24", SHOT)).unwrap();
+    assert_eq!(puzzle.solution.len(), 1);
+    // not legal here, and no solution at all
+    assert!(Puzzle::parse(&format!("{} 5-9", SHOT)).is_none());
+    assert!(Puzzle::parse(SHOT).is_none());
+
+    let path = temp_path("puzzles.txt");
+    save_puzzles(&path, std::slice::from_ref(&puzzle)).unwrap();
+    let loaded = load_puzzles(&path);
+    fs::write(&path, "B:W9:B5 1-2\n").unwrap();
+    let bad = load_puzzles(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap()[0].to_line(), puzzle.to_line());
+    assert!(bad.is_err());
+}
+
+#[test]
+fn every_jump_of_the_solution_is_checked() {
+    let puzzle = Puzzle::parse(&format!("{} 8x15x24", SHOT)).unwrap();
+    let mut session = PuzzleSession::new(puzzle);
+
+    // the other capture is a mistake and changes nothing
+    assert!(session.try_step(square(5), square(14)).is_err());
+    assert_eq!(session.mistakes, 1);
+    assert_eq!(session.board.board_state, session.puzzle.board.board_state);
+
+    assert!(session.try_step(square(8), square(15)).is_ok());
+    assert!(!session.is_solved());
+    // the second jump has to take the man on 19
+    assert!(session.try_step(square(15), square(22)).is_err());
+    assert_eq!(session.mistakes, 2);
+    assert!(session.try_step(square(15), square(24)).is_ok());
+    assert!(session.is_solved());
+    assert_eq!(session.reply(), None);
+}
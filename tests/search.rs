@@ -0,0 +1,96 @@
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use hw4::difficulty::Difficulty;
+use hw4::evaluator::{MaterialEvaluator, WeightedEvaluator};
+use hw4::pdn::{from_fen, move_to_notation, START_FEN};
+use hw4::search::{best_move, best_move_with_stats, multi_pv, SearchOptions, SearchStats, Searcher, INFINITY, WIN_SCORE};
+
+#[test]
+fn the_search_finds_the_double_jump() {
+    // 8x15x24 takes two men where 5x14 takes one; the same shot for red
+    // is the board turned around
+    for (fen, expected) in [("B:W9,11,19,K32:B1,5,8", "8x15x24"), ("W:W25,28,32:B14,22,24,K1", "25x18x9")] {
+        let mut board = from_fen(fen).unwrap();
+        let (j, i, m) = board.get_best_move(4, board.current_turn).unwrap();
+        assert_eq!(move_to_notation(j, i, &m), expected, "{}", fen);
+    }
+}
+
+#[test]
+fn scores_are_for_the_side_to_move() {
+    let searcher = Searcher::new(&MaterialEvaluator);
+    let score = |fen: &str, depth: usize| searcher.negamax(&from_fen(fen).unwrap(), depth, 0, -INFINITY, INFINITY);
+
+    // a man up is good for whoever has it, whichever side is to move
+    assert!(score("B:W22:B1,2", 0) > 0);
+    assert!(score("W:W22:B1,2", 0) < 0);
+    assert!(score("W:W21,22:B1", 0) > 0);
+    assert!(score("B:W21,22:B1", 0) < 0);
+    // taking the last piece wins, having no move loses
+    assert_eq!(score("B:W11:B8", 2), WIN_SCORE - 1);
+    assert_eq!(score("W:WK4:B8,11", 2), -WIN_SCORE);
+}
+
+#[test]
+fn the_same_seed_plays_the_same_moves() {
+    let evaluator = WeightedEvaluator::default();
+    // noise, random picks among near-best moves and blunders all come from the seed
+    let options = SearchOptions {
+        threads: 4,
+        time_budget: Some(Duration::from_millis(1)),
+        ..Difficulty::Beginner.search_options(1)
+    }.deterministic(7);
+    for fen in [START_FEN, "W:W21-24,26-32:B1-8,10-13", "B:W18,22,K30:B1,K14,15,9"] {
+        let board = from_fen(fen).unwrap();
+        let first = best_move(&board, &options, &evaluator).unwrap();
+        for _ in 0..3 {
+            assert_eq!(best_move(&board, &options, &evaluator).unwrap(), first, "{}", fen);
+        }
+    }
+}
+
+#[test]
+fn principal_variations_come_best_first() {
+    let board = from_fen(START_FEN).unwrap();
+    let options = SearchOptions::fixed_depth(4);
+    let (lines, stats) = multi_pv(&board, &options, &WeightedEvaluator::default(), 3, &AtomicBool::new(false));
+    assert_eq!(lines.len(), 3);
+    assert_eq!(stats.depth(), 4);
+    assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    for line in &lines {
+        assert!(!line.moves.is_empty());
+    }
+
+    // the best line starts with the move a plain search plays
+    let (j, i, m) = best_move(&board, &options, &WeightedEvaluator::default()).unwrap();
+    let (lj, li, lm) = &lines[0].moves[0];
+    assert_eq!(move_to_notation(*lj, *li, lm), move_to_notation(j, i, &m));
+}
+
+#[test]
+fn progress_is_reported_once_per_iteration() {
+    let board = from_fen(START_FEN).unwrap();
+    let mut reports = Vec::new();
+    let mut report = |stats: &SearchStats| reports.push(stats.clone());
+    let (result, stats) = best_move_with_stats(&board, &SearchOptions::fixed_depth(5), &MaterialEvaluator, &AtomicBool::new(false), &mut report);
+    assert!(result.is_ok());
+    assert_eq!(reports.len(), 5);
+
+    let mut nodes = 0;
+    for (n, report) in reports.iter().enumerate() {
+        let depths: Vec<usize> = report.iterations.iter().map(|iteration| iteration.depth).collect();
+        assert_eq!(depths, (1..=n + 1).collect::<Vec<_>>());
+        assert!(report.nodes > nodes);
+        nodes = report.nodes;
+        // each iteration counts its own nodes, which add up to the total
+        assert!(report.iterations.iter().all(|iteration| iteration.nodes > 0));
+        assert_eq!(report.iterations.iter().map(|iteration| iteration.nodes).sum::<u64>(), report.nodes);
+        assert!(report.first_move_cutoffs <= report.beta_cutoffs);
+        assert!(report.seldepth > n);
+    }
+    assert!(stats.beta_cutoffs > 0);
+    // the last report is the search as it finished
+    let last = reports.last().unwrap();
+    assert_eq!((stats.nodes, stats.beta_cutoffs, &stats.iterations), (last.nodes, last.beta_cutoffs, &last.iterations));
+}
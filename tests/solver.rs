@@ -0,0 +1,46 @@
+use hw4::checkers::Move;
+use hw4::pdn::{from_fen, move_to_notation, START_FEN};
+use hw4::solver::{solve, SolverOptions, Verdict};
+use hw4::tablebase::{generate, TbValue};
+
+fn notation(line: &[(usize, usize, Move)]) -> Vec<String> {
+    line.iter().map(|(j, i, m)| move_to_notation(*j, *i, m)).collect()
+}
+
+#[test]
+fn short_endgames_are_solved() {
+    let options = SolverOptions::default();
+
+    let solution = solve(&from_fen("B:W11:B8").unwrap(), &options);
+    assert_eq!(solution.verdict, Verdict::Win);
+    assert_eq!(notation(&solution.line), ["8x15"]);
+
+    // boxed in with no move at all
+    let solution = solve(&from_fen("W:WK4:B8,11").unwrap(), &options);
+    assert_eq!(solution.verdict, Verdict::Loss);
+}
+
+#[test]
+fn the_solver_agrees_with_the_tablebase() {
+    let tablebase = generate(2);
+    let board = from_fen("B:WK29:BK4").unwrap();
+    let Some(TbValue::Win(plies)) = tablebase.probe(&board) else {
+        panic!("expected a win, got {:?}", tablebase.probe(&board));
+    };
+    let solution = solve(&board, &SolverOptions::default());
+    assert_eq!(solution.verdict, Verdict::Win);
+    // the winner's moves with the longest defence in between
+    assert_eq!(solution.line.len(), plies as usize);
+}
+
+#[test]
+fn limits_give_draws_and_unknowns() {
+    // no win fits in two plies
+    let short = SolverOptions { max_plies: 2, ..SolverOptions::default() };
+    assert_eq!(solve(&from_fen("B:WK29:BK4").unwrap(), &short).verdict, Verdict::Draw);
+
+    let tiny = SolverOptions { node_budget: 100, ..SolverOptions::default() };
+    let solution = solve(&from_fen(START_FEN).unwrap(), &tiny);
+    assert_eq!(solution.verdict, Verdict::Unknown);
+    assert!(solution.line.is_empty());
+}
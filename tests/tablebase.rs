@@ -0,0 +1,60 @@
+mod common;
+
+use std::fs;
+use std::sync::atomic::AtomicBool;
+
+use hw4::evaluator::MaterialEvaluator;
+use hw4::pdn::from_fen;
+use hw4::search::{best_move_with_stats, SearchOptions};
+use hw4::solver::{solve, SolverOptions, Verdict};
+use hw4::tablebase::{generate, Tablebase, TbValue};
+
+use common::temp_path;
+
+#[test]
+fn small_endgames_have_known_values() {
+    let tablebase = generate(3);
+
+    // black takes red's last piece
+    assert_eq!(tablebase.probe(&from_fen("B:W11:B8").unwrap()), Some(TbValue::Win(1)));
+    // the red king in the corner is boxed in by two men and cannot move
+    assert_eq!(tablebase.probe(&from_fen("W:WK4:B8,11").unwrap()), Some(TbValue::Loss(0)));
+    // outside the table
+    assert_eq!(tablebase.probe(&from_fen("B:W21-32:B1-12").unwrap()), None);
+    assert_eq!(tablebase.probe(&from_fen("B:W:B8").unwrap()), None);
+}
+
+#[test]
+fn tablebases_survive_a_save_and_load() {
+    let tablebase = generate(2);
+    let path = temp_path("tablebase.tb");
+    tablebase.save(&path).unwrap();
+    let loaded = Tablebase::load(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.max_pieces, 2);
+    assert_eq!(loaded.len(), tablebase.len());
+
+    let board = from_fen("B:W11:B8").unwrap();
+    assert_eq!(loaded.probe(&board), tablebase.probe(&board));
+}
+
+#[test]
+fn the_search_and_solver_probe_the_tablebase_they_are_given() {
+    let tablebase: &'static Tablebase = Box::leak(Box::new(generate(2)));
+    let board = from_fen("B:WK29:BK4").unwrap();
+
+    let hits = |tablebase| {
+        let options = SearchOptions { tablebase, ..SearchOptions::fixed_depth(3) };
+        let (_, stats) = best_move_with_stats(&board, &options, &MaterialEvaluator, &AtomicBool::new(false), &mut |_| ());
+        stats.tablebase_hits
+    };
+    assert!(hits(Some(tablebase)) > 0);
+    assert_eq!(hits(None), 0);
+
+    // known straight away, where the solver would otherwise search for it
+    let options = SolverOptions { tablebase: Some(tablebase), ..SolverOptions::default() };
+    let solution = solve(&board, &options);
+    assert_eq!(solution.verdict, Verdict::Win);
+    assert!(solution.nodes < solve(&board, &SolverOptions::default()).nodes);
+}
@@ -0,0 +1,46 @@
+use hw4::difficulty::Difficulty;
+use hw4::game::Game;
+use hw4::pdn::{from_fen, parse_move, to_fen, START_FEN};
+use hw4::terminal::{render_board, Command};
+
+#[test]
+fn commands_parse() {
+    assert_eq!(Command::parse("11-15"), Ok(Command::Move("11-15".to_string())));
+    assert_eq!(Command::parse(" 15x22x29 "), Ok(Command::Move("15x22x29".to_string())));
+    assert_eq!(Command::parse("undo"), Ok(Command::Undo));
+    assert_eq!(Command::parse("U"), Ok(Command::Undo));
+    assert_eq!(Command::parse("hint"), Ok(Command::Hint));
+    assert_eq!(Command::parse("level Expert"), Ok(Command::Level(Difficulty::Expert)));
+    assert_eq!(Command::parse("quit"), Ok(Command::Quit));
+}
+
+#[test]
+fn bad_commands_are_errors() {
+    assert_eq!(Command::parse("level foo"), Err("unknown difficulty foo".to_string()));
+    assert!(Command::parse("level").is_err());
+    assert!(Command::parse("castle").unwrap_err().starts_with("unknown command castle"));
+    assert!(Command::parse("").is_err());
+}
+
+#[test]
+fn undo_takes_back_moves_until_the_start() {
+    let start = from_fen(START_FEN).unwrap();
+    let mut game = Game::from_position(start);
+    assert_eq!(game.undo(), None);
+
+    let (j, i, m) = parse_move(&game.checkers, "11-15").unwrap();
+    game.play(j, i, &m).unwrap();
+    assert_eq!(game.undo(), Some((j, i, m)));
+    assert_eq!(to_fen(&game.checkers), to_fen(&start));
+    assert_eq!(game.undo(), None);
+}
+
+#[test]
+fn the_board_shows_pieces_and_empty_squares() {
+    let text = render_board(&from_fen(START_FEN).unwrap());
+    let rows: Vec<&str> = text.lines().collect();
+    assert_eq!(rows.len(), 9);
+    assert_eq!(rows[0], "  .  r  .  r  .  r  .  r");
+    assert_eq!(rows[3], " 20  . 19  . 18  . 17  .");
+    assert_eq!(rows[8], "black to move");
+}
@@ -0,0 +1,43 @@
+use hw4::search::WIN_SCORE;
+use hw4::tt::{Bound, TranspositionTable, TtEntry};
+
+fn entry(score: i32, depth: u8) -> TtEntry {
+    TtEntry { score, depth, bound: Bound::Lower, best_move: Some(3) }
+}
+
+#[test]
+fn stored_entries_come_back() {
+    let tt = TranspositionTable::new(1000);
+    let hash = 0x1234_5678_9abc_def0;
+    assert_eq!(tt.probe(hash, 0), None);
+
+    tt.store(hash, 0, entry(-250, 6));
+    assert_eq!(tt.probe(hash, 0), Some(entry(-250, 6)));
+    // another position sharing the slot is a miss, not a wrong hit
+    assert_eq!(tt.probe(hash + 1024, 0), None);
+
+    let no_move = TtEntry { score: 0, depth: 1, bound: Bound::Exact, best_move: None };
+    tt.store(7, 0, no_move);
+    assert_eq!(tt.probe(7, 0), Some(no_move));
+}
+
+#[test]
+fn deeper_results_are_kept() {
+    let tt = TranspositionTable::new(16);
+    tt.store(42, 0, entry(10, 8));
+    tt.store(42, 0, entry(99, 3));
+    assert_eq!(tt.probe(42, 0), Some(entry(10, 8)));
+    tt.store(42, 0, entry(20, 9));
+    assert_eq!(tt.probe(42, 0), Some(entry(20, 9)));
+}
+
+#[test]
+fn mate_scores_follow_the_ply() {
+    let tt = TranspositionTable::new(16);
+    // a win 5 plies from the root, found at ply 2, is 3 plies from the position
+    tt.store(9, 2, entry(WIN_SCORE - 5, 4));
+    assert_eq!(tt.probe(9, 2).unwrap().score, WIN_SCORE - 5);
+    assert_eq!(tt.probe(9, 6).unwrap().score, WIN_SCORE - 9);
+    tt.store(10, 2, entry(-WIN_SCORE + 5, 4));
+    assert_eq!(tt.probe(10, 6).unwrap().score, -WIN_SCORE + 9);
+}
@@ -0,0 +1,85 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hw4::checkers::{Checkers, Move};
+use hw4::evaluator::MaterialEvaluator;
+use hw4::pdn::{from_fen, START_FEN};
+use hw4::search::{best_move, SearchOptions};
+use hw4::worker::SearchWorker;
+
+// Deep enough that no search finishes unless it is stopped.
+fn endless() -> SearchOptions {
+    SearchOptions::fixed_depth(30)
+}
+
+fn is_legal(board: &Checkers, (j, i, m): (usize, usize, Move)) -> bool {
+    board.get_all_moves().contains(&(j, i, m))
+}
+
+// The position after the reply a ponder search expects to `board`.
+fn predicted(board: &Checkers) -> Checkers {
+    let (j, i, m) = best_move(board, &SearchOptions::fixed_depth(4), &MaterialEvaluator).unwrap();
+    let mut after = *board;
+    after.make_move_from_enum(j, i, &m).unwrap();
+    after
+}
+
+#[test]
+fn a_ponder_hit_keeps_searching_until_the_clock_runs_out() {
+    let board = from_fen(START_FEN).unwrap();
+    let expected = predicted(&board);
+    let mut worker = SearchWorker::ponder(board, endless(), MaterialEvaluator);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !worker.is_searching(&expected) {
+        assert!(Instant::now() < deadline, "never started on the predicted position");
+        thread::sleep(Duration::from_millis(5));
+    }
+    // without a clock it goes on for as long as the opponent thinks
+    thread::sleep(Duration::from_millis(200));
+    assert!(worker.poll().is_none());
+    assert!(worker.stats().is_some_and(|stats| !stats.iterations.is_empty()));
+
+    let start = Instant::now();
+    worker.start_clock(Some(Duration::from_millis(100)));
+    let result = worker.wait().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(is_legal(&expected, result));
+}
+
+#[test]
+fn a_ponder_miss_is_dropped_for_a_new_search() {
+    let board = from_fen(START_FEN).unwrap();
+    let expected = predicted(&board);
+    // any reply but the predicted one
+    let actual = board.get_all_moves().into_iter().map(|(j, i, m)| {
+        let mut after = board;
+        after.make_move_from_enum(j, i, &m).unwrap();
+        after
+    }).find(|after| after.board_state != expected.board_state).unwrap();
+
+    let worker = SearchWorker::ponder(board, endless(), MaterialEvaluator);
+    assert!(!worker.is_searching(&actual));
+    // the cancelled ponder search still answers, and promptly
+    let start = Instant::now();
+    worker.cancel();
+    assert!(worker.wait().is_ok());
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let options = SearchOptions { time_budget: Some(Duration::from_millis(100)), ..endless() };
+    let result = SearchWorker::spawn(actual, options, MaterialEvaluator).wait().unwrap();
+    assert!(is_legal(&actual, result));
+}
+
+#[test]
+fn a_cancelled_search_returns_its_best_move_so_far() {
+    let board = from_fen("W:W21-32:B1-8,10-13").unwrap();
+    let worker = SearchWorker::spawn(board, endless(), MaterialEvaluator);
+    thread::sleep(Duration::from_millis(200));
+    assert!(worker.poll().is_none());
+
+    let start = Instant::now();
+    worker.cancel();
+    let result = worker.wait().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(is_legal(&board, result));
+}